@@ -8,6 +8,7 @@ impl wlroots::InputManagerHandler for InputManager {}
 
 fn main() {
     wlroots::CompositorBuilder::new()
-        .build_auto((), Box::new(InputManager), Box::new(OutputManager))
-        .run()
+        .build((), Box::new(InputManager), Box::new(OutputManager))
+        .and_then(|compositor| compositor.run())
+        .expect("Compositor failed")
 }
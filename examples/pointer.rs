@@ -3,11 +3,13 @@ extern crate wlroots;
 
 use std::cell::RefCell;
 use std::rc::Rc;
-use wlroots::{AxisEvent, ButtonEvent, Compositor, CompositorBuilder, Cursor, InputManagerHandler,
-              KeyEvent, KeyboardHandler, MotionEvent, OutputBuilder, OutputBuilderResult,
-              OutputHandler, OutputLayout, OutputManagerHandler, PointerHandler, XCursorTheme};
+use wlroots::{AbsoluteMotionEvent, AxisEvent, ButtonEvent, Compositor, CompositorBuilder, Cursor,
+              InputManagerHandler, KeyEvent, KeyboardHandler, MotionEvent, OutputBuilder,
+              OutputBuilderResult, OutputHandler, OutputLayout, OutputManagerHandler,
+              PointerHandler, XCursorTheme};
 use wlroots::types::{KeyboardHandle, OutputHandle, PointerHandle};
 use wlroots::wlroots_sys::gl;
+use wlroots::wlroots_sys::wlr_axis_orientation::WLR_AXIS_ORIENTATION_VERTICAL;
 use wlroots::wlroots_sys::wlr_button_state::WLR_BUTTON_RELEASED;
 use wlroots::xkbcommon::xkb::keysyms::KEY_Escape;
 
@@ -27,8 +29,6 @@ impl State {
     }
 }
 
-compositor_data!(State);
-
 struct OutputManager;
 
 struct Output;
@@ -39,13 +39,13 @@ struct Pointer;
 
 struct ExKeyboardHandler;
 
-impl OutputManagerHandler for OutputManager {
+impl OutputManagerHandler<State> for OutputManager {
     fn output_added<'output>(&mut self,
-                             compositor: &mut Compositor,
+                             compositor: &mut Compositor<State>,
                              builder: OutputBuilder<'output>)
-                             -> Option<OutputBuilderResult<'output>> {
+                             -> Option<OutputBuilderResult<'output, State>> {
         let result = builder.build_best_mode(Output);
-        let state: &mut State = compositor.into();
+        let state = &mut compositor.data;
         let cursor = &mut state.cursor;
         // TODO use output config if present instead of auto
         {
@@ -68,9 +68,9 @@ impl OutputManagerHandler for OutputManager {
     }
 }
 
-impl KeyboardHandler for ExKeyboardHandler {
+impl KeyboardHandler<State> for ExKeyboardHandler {
     fn on_key(&mut self,
-              compositor: &mut Compositor,
+              compositor: &mut Compositor<State>,
               _: &mut KeyboardHandle,
               key_event: &mut KeyEvent) {
         for key in key_event.input_keys() {
@@ -81,21 +81,37 @@ impl KeyboardHandler for ExKeyboardHandler {
     }
 }
 
-impl PointerHandler for Pointer {
+impl PointerHandler<State> for Pointer {
     fn on_motion(&mut self,
-                 compositor: &mut Compositor,
+                 compositor: &mut Compositor<State>,
                  _: &mut PointerHandle,
                  event: &MotionEvent) {
-        let state: &mut State = compositor.into();
+        let state = &mut compositor.data;
         let (delta_x, delta_y) = event.delta();
         state.cursor.move_to(&event.device(), delta_x, delta_y);
     }
 
+    fn on_motion_absolute(&mut self,
+                          compositor: &mut Compositor<State>,
+                          _: &mut PointerHandle,
+                          event: &AbsoluteMotionEvent) {
+        let state = &mut compositor.data;
+        let coords = match *state.cursor.output_layout() {
+            Some(ref layout) => event.layout_coords(&layout.borrow()),
+            None => return
+        };
+        let (x, y) = match coords {
+            Some(coords) => coords,
+            None => return
+        };
+        state.cursor.warp(Some(event.device()), x, y);
+    }
+
     fn on_button(&mut self,
-                 compositor: &mut Compositor,
+                 compositor: &mut Compositor<State>,
                  _: &mut PointerHandle,
                  event: &ButtonEvent) {
-        let state: &mut State = compositor.into();
+        let state = &mut compositor.data;
         if event.state() == WLR_BUTTON_RELEASED {
             state.color = state.default_color;
         } else {
@@ -104,8 +120,14 @@ impl PointerHandler for Pointer {
         }
     }
 
-    fn on_axis(&mut self, compositor: &mut Compositor, _: &mut PointerHandle, event: &AxisEvent) {
-        let state: &mut State = compositor.into();
+    fn on_axis(&mut self,
+               compositor: &mut Compositor<State>,
+               _: &mut PointerHandle,
+               event: &AxisEvent) {
+        if event.orientation() != WLR_AXIS_ORIENTATION_VERTICAL {
+            return
+        }
+        let state = &mut compositor.data;
         for color_byte in &mut state.default_color[..3] {
             *color_byte += if event.delta() > 0.0 { -0.05 } else { 0.05 };
             if *color_byte > 1.0 {
@@ -119,9 +141,9 @@ impl PointerHandler for Pointer {
     }
 }
 
-impl OutputHandler for Output {
-    fn output_frame(&mut self, compositor: &mut Compositor, output: &mut OutputHandle) {
-        let state: &mut State = compositor.into();
+impl OutputHandler<State> for Output {
+    fn output_frame(&mut self, compositor: &mut Compositor<State>, output: &mut OutputHandle) {
+        let state = &mut compositor.data;
         output.make_current();
         unsafe {
             gl::ClearColor(state.color[0], state.color[1], state.color[2], 1.0);
@@ -131,18 +153,18 @@ impl OutputHandler for Output {
     }
 }
 
-impl InputManagerHandler for InputManager {
+impl InputManagerHandler<State> for InputManager {
     fn pointer_added(&mut self,
-                     _: &mut Compositor,
+                     _: &mut Compositor<State>,
                      _: &mut PointerHandle)
-                     -> Option<Box<PointerHandler>> {
+                     -> Option<Box<PointerHandler<State>>> {
         Some(Box::new(Pointer))
     }
 
     fn keyboard_added(&mut self,
-                      _: &mut Compositor,
+                      _: &mut Compositor<State>,
                       _: &mut KeyboardHandle)
-                      -> Option<Box<KeyboardHandler>> {
+                      -> Option<Box<KeyboardHandler<State>>> {
         Some(Box::new(ExKeyboardHandler))
     }
 }
@@ -160,5 +182,5 @@ fn main() {
     let compositor = CompositorBuilder::new().build_auto(State::new(cursor),
                                                          Box::new(InputManager),
                                                          Box::new(OutputManager));
-    compositor.run();
+    compositor.run().expect("Compositor failed");
 }
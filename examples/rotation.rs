@@ -5,7 +5,9 @@ use std::env;
 use std::time::Instant;
 
 use wlroots::{Compositor, CompositorBuilder, InputManagerHandler, KeyEvent, KeyboardHandler,
-              OutputBuilder, OutputBuilderResult, OutputHandler, OutputManagerHandler};
+              KeyboardModifier, OutputBuilder, OutputBuilderResult, OutputHandler,
+              OutputManagerHandler};
+use wlroots::bindings::{Binding, KeyBindings, DEFAULT_MODE};
 use wlroots::render::{Texture, TextureFormat};
 use wlroots::types::{KeyboardHandle, OutputHandle};
 use wlroots::wlroots_sys::wl_output_transform;
@@ -41,8 +43,6 @@ struct CompositorState {
     y_vel: f32
 }
 
-compositor_data!(CompositorState);
-
 impl CompositorState {
     fn new(rotation: wl_output_transform) -> Self {
         CompositorState {
@@ -63,14 +63,16 @@ struct Output;
 
 struct InputManager;
 
-struct KeyboardManager;
+struct KeyboardManager {
+    bindings: KeyBindings<CompositorState>
+}
 
-impl OutputManagerHandler for OutputManager {
+impl OutputManagerHandler<CompositorState> for OutputManager {
     fn output_added<'output>(&mut self,
-                             compositor: &mut Compositor,
+                             compositor: &mut Compositor<CompositorState>,
                              builder: OutputBuilder<'output>)
-                             -> Option<OutputBuilderResult<'output>> {
-        let compositor_data: &mut CompositorState = compositor.into();
+                             -> Option<OutputBuilderResult<'output, CompositorState>> {
+        let compositor_data = &mut compositor.data;
         let output = Output;
         let res = builder.build_best_mode(output);
         res.output.transform(compositor_data.rotation);
@@ -78,16 +80,16 @@ impl OutputManagerHandler for OutputManager {
     }
 }
 
-impl OutputHandler for Output {
-    fn output_frame(&mut self, compositor: &mut Compositor, output: &mut OutputHandle) {
+impl OutputHandler<CompositorState> for Output {
+    fn output_frame(&mut self,
+                    compositor: &mut Compositor<CompositorState>,
+                    output: &mut OutputHandle) {
         let (width, height) = output.effective_resolution();
         let renderer = compositor
             .gles2
             .as_mut()
             .expect("Compositor was not loaded with gles2 renderer");
-        let compositor_data: &mut CompositorState = (&mut compositor.data)
-            .downcast_mut()
-            .unwrap();
+        let compositor_data = &mut compositor.data;
         let now = Instant::now();
         let delta = now.duration_since(compositor_data.last_frame);
         let seconds_delta = delta.as_secs() as f32;
@@ -115,32 +117,42 @@ impl OutputHandler for Output {
     }
 }
 
-impl InputManagerHandler for InputManager {
+impl InputManagerHandler<CompositorState> for InputManager {
     fn keyboard_added(&mut self,
-                      _: &mut Compositor,
+                      _: &mut Compositor<CompositorState>,
                       _: &mut KeyboardHandle)
-                      -> Option<Box<KeyboardHandler>> {
-        Some(Box::new(KeyboardManager))
+                      -> Option<Box<KeyboardHandler<CompositorState>>> {
+        Some(Box::new(KeyboardManager::new()))
     }
 }
 
-impl KeyboardHandler for KeyboardManager {
+impl KeyboardManager {
+    fn new() -> Self {
+        let mut bindings = KeyBindings::new();
+        let mut bind = |keysym, x_diff, y_diff| {
+            bindings.bind(DEFAULT_MODE,
+                          Binding::new(KeyboardModifier::empty(), keysym),
+                          move |compositor: &mut Compositor<CompositorState>| {
+                              update_velocities(&mut compositor.data, x_diff, y_diff)
+                          })
+        };
+        bind(keysyms::KEY_Left, -16.0, 0.0);
+        bind(keysyms::KEY_Right, 16.0, 0.0);
+        bind(keysyms::KEY_Up, 0.0, -16.0);
+        bind(keysyms::KEY_Down, 0.0, 16.0);
+        bindings.bind(DEFAULT_MODE,
+                      Binding::new(KeyboardModifier::empty(), keysyms::KEY_Escape),
+                      |compositor| compositor.terminate());
+        KeyboardManager { bindings }
+    }
+}
+
+impl KeyboardHandler<CompositorState> for KeyboardManager {
     fn on_key(&mut self,
-              compositor: &mut Compositor,
-              _: &mut KeyboardHandle,
+              compositor: &mut Compositor<CompositorState>,
+              keyboard: &mut KeyboardHandle,
               key_event: &mut KeyEvent) {
-        let keys = key_event.input_keys();
-
-        for key in keys {
-            match key {
-                keysyms::KEY_Escape => compositor.terminate(),
-                keysyms::KEY_Left => update_velocities(compositor.into(), -16.0, 0.0),
-                keysyms::KEY_Right => update_velocities(compositor.into(), 16.0, 0.0),
-                keysyms::KEY_Up => update_velocities(compositor.into(), 0.0, -16.0),
-                keysyms::KEY_Down => update_velocities(compositor.into(), 0.0, 16.0),
-                _ => {}
-            }
-        }
+        self.bindings.handle_key(compositor, keyboard, key_event);
     }
 }
 
@@ -175,7 +187,7 @@ fn main() {
         .build_auto(compositor_state, input_manager, output_manager);
     {
         let gles2 = &mut compositor.gles2.as_mut().unwrap();
-        let compositor_data: &mut CompositorState = (&mut compositor.data).downcast_mut().unwrap();
+        let compositor_data = &mut compositor.data;
         compositor_data.cat_texture = gles2
             .create_texture()
             .map(|mut cat_texture| {
@@ -187,5 +199,5 @@ fn main() {
                      cat_texture
                  })
     }
-    compositor.run();
+    compositor.run().expect("Compositor failed");
 }
@@ -93,4 +93,5 @@ fn main() {
     CompositorBuilder::new()
         .build_auto((), Box::new(InputManager), Box::new(OutputManager))
         .run()
+        .expect("Compositor failed")
 }
@@ -0,0 +1,127 @@
+//! Selection of the backend that the compositor runs on.
+//! Pass one of these to `CompositorBuilder::backend` before building.
+
+use std::ffi::CString;
+use std::ptr;
+
+use wayland_sys::server::wl_display;
+use wlroots_sys::{wlr_backend, wlr_backend_autocreate, wlr_backend_destroy,
+                  wlr_headless_add_output, wlr_headless_backend_create, wlr_multi_backend_add,
+                  wlr_multi_backend_create, wlr_wl_backend_create, wlr_wl_output_create,
+                  wlr_x11_backend_create};
+
+/// The backend that the compositor gets its outputs and inputs from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// Let wlroots pick the backend based on the environment
+    /// (e.g Wayland if `WAYLAND_DISPLAY` is set, X11 if `DISPLAY` is set,
+    /// otherwise DRM).
+    Auto,
+    /// A backend that doesn't render anywhere and has no input devices.
+    ///
+    /// Useful for testing on machines without a GPU.
+    Headless {
+        /// The (width, height) of each output to create.
+        outputs: Vec<(u32, u32)>
+    },
+    /// Runs nested inside of another Wayland compositor.
+    Wayland {
+        /// The number of windows to open in the parent compositor.
+        outputs: usize
+    },
+    /// Runs nested inside of an X11 server.
+    X11 {
+        /// The X11 display to connect to.
+        /// If none is given, `DISPLAY` is used.
+        display: Option<String>
+    },
+    /// Combines several backends, so that the outputs and inputs of all of
+    /// them are available at once.
+    Multi(Vec<Backend>)
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::Auto
+    }
+}
+
+impl Backend {
+    /// Creates the backend on the display.
+    ///
+    /// Returns a null pointer if the backend, or one of the backends it is
+    /// made of, could not be created (e.g the X11 display name contains a
+    /// NUL byte).
+    pub(crate) unsafe fn create(&self, display: *mut wl_display) -> *mut wlr_backend {
+        use self::Backend::*;
+        let display = display as *mut _;
+        match *self {
+            Auto => wlr_backend_autocreate(display),
+            Headless { ref outputs } => {
+                let backend = wlr_headless_backend_create(display);
+                if backend.is_null() {
+                    wlr_log!(L_ERROR, "Could not create headless backend");
+                    return backend
+                }
+                for &(width, height) in outputs {
+                    if wlr_headless_add_output(backend, width, height).is_null() {
+                        wlr_log!(L_ERROR, "Could not add {}x{} headless output", width, height);
+                        wlr_backend_destroy(backend);
+                        return ptr::null_mut()
+                    }
+                }
+                backend
+            }
+            Wayland { outputs } => {
+                let backend = wlr_wl_backend_create(display);
+                if backend.is_null() {
+                    wlr_log!(L_ERROR, "Could not create nested Wayland backend");
+                    return backend
+                }
+                // NOTE The outputs are only requested here,
+                // the windows are opened once the backend is started.
+                for _ in 0..outputs {
+                    wlr_wl_output_create(backend);
+                }
+                backend
+            }
+            X11 { display: ref display_name } => {
+                let display_name = match display_name.clone().map(CString::new) {
+                    Some(Ok(name)) => Some(name),
+                    Some(Err(_)) => {
+                        wlr_log!(L_ERROR,
+                                 "X11 display name {:?} contains a NUL byte",
+                                 display_name);
+                        return ptr::null_mut()
+                    }
+                    None => None
+                };
+                let display_ptr = display_name.as_ref()
+                    .map(|name| name.as_ptr())
+                    .unwrap_or(ptr::null());
+                let backend = wlr_x11_backend_create(display, display_ptr);
+                if backend.is_null() {
+                    wlr_log!(L_ERROR, "Could not create X11 backend");
+                }
+                backend
+            }
+            Multi(ref backends) => {
+                let multi = wlr_multi_backend_create(display);
+                if multi.is_null() {
+                    wlr_log!(L_ERROR, "Could not create multi backend");
+                    return multi
+                }
+                for backend in backends {
+                    let sub_backend = backend.create(display as *mut _);
+                    if sub_backend.is_null() {
+                        // Destroys the backends that were already added as well.
+                        wlr_backend_destroy(multi);
+                        return ptr::null_mut()
+                    }
+                    wlr_multi_backend_add(multi, sub_backend);
+                }
+                multi
+            }
+        }
+    }
+}
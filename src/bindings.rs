@@ -0,0 +1,895 @@
+//! Declarative key and pointer button bindings.
+//!
+//! Register what should happen for a key (e.g Logo+Return) in a
+//! `KeyBindings`, then give it every key event from
+//! `KeyboardHandler::on_key`:
+//!
+//! ```rust,no_run
+//! # extern crate wlroots;
+//! # use wlroots::{Compositor, KeyboardHandle, KeyboardHandler, KeyEvent};
+//! use wlroots::bindings::{Binding, KeyBindings, DEFAULT_MODE};
+//! use wlroots::KeyboardModifier;
+//! use wlroots::xkbcommon::xkb::keysyms::*;
+//!
+//! struct Keyboard {
+//!     bindings: KeyBindings
+//! }
+//!
+//! impl KeyboardHandler for Keyboard {
+//!     fn on_key(&mut self,
+//!               compositor: &mut Compositor,
+//!               keyboard: &mut KeyboardHandle,
+//!               key: &mut KeyEvent) {
+//!         if !self.bindings.handle_key(compositor, keyboard, key) {
+//!             // Not bound, send it to the focused client
+//!         }
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut bindings = KeyBindings::new();
+//! bindings.bind(DEFAULT_MODE,
+//!               Binding::new(KeyboardModifier::WLR_MODIFIER_LOGO, KEY_Escape),
+//!               |compositor| compositor.terminate());
+//! // Logo+r enters the resize mode, where Escape leaves it again.
+//! bindings.bind_mode(DEFAULT_MODE,
+//!                    Binding::new(KeyboardModifier::WLR_MODIFIER_LOGO, KEY_r),
+//!                    "resize");
+//! bindings.bind_mode("resize",
+//!                    Binding::new(KeyboardModifier::empty(), KEY_Escape),
+//!                    DEFAULT_MODE);
+//! let keyboard = Keyboard { bindings };
+//! # }
+//! ```
+//!
+//! `PointerBindings` does the same for pointer buttons, including dragging
+//! with a button held (e.g Logo+left drag to move a window).
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use compositor::Compositor;
+use event_loop::duration_to_ms;
+use events::key_events::{Key, KeyEvent};
+use events::pointer_events::ButtonEvent;
+use types::keyboard::{KeyboardHandle, KeyboardModifier};
+
+use wlroots_sys::{wlr_button_state, wlr_input_device, wlr_key_state};
+use wlroots_sys::wlr_button_state::*;
+use wlroots_sys::wlr_key_state::*;
+
+/// The mode the bindings start in.
+pub const DEFAULT_MODE: &str = "default";
+
+type BindingAction<T> = FnMut(&mut Compositor<T>);
+type ButtonAction<T> = FnMut(&mut Compositor<T>, (f64, f64));
+type DragAction<T> = FnMut(&mut Compositor<T>, &Drag);
+
+/// A key together with the modifiers that have to be held for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub modifiers: KeyboardModifier,
+    /// The keysym of the key without any modifiers applied
+    /// (e.g `KEY_a` for Shift+a).
+    pub keysym: Key
+}
+
+/// The keys that trigger a binding.
+///
+/// This is one key combo, or several that have to be pressed one after the
+/// other (e.g Ctrl+x then Ctrl+c) within the chord timeout of the bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Binding {
+    chord: Vec<KeyCombo>,
+    release: bool
+}
+
+enum Action<T> {
+    Run(Box<BindingAction<T>>),
+    SwitchMode(String)
+}
+
+struct BindingEntry<T> {
+    binding: Binding,
+    action: Action<T>
+}
+
+/// What a key does to the bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeyMatch {
+    /// The key isn't bound, it should be sent to clients.
+    Forward,
+    /// The key was consumed without running an action (e.g the start of a
+    /// chord, or a mode switch).
+    Consumed,
+    /// The action of the binding at the index in the mode has to be run.
+    Run(String, usize)
+}
+
+/// A set of key bindings, grouped into named modes.
+///
+/// Only the bindings of the current mode are matched, which starts out as
+/// `DEFAULT_MODE`.
+///
+/// Call `reset` when a release can't arrive anymore (e.g the keyboard is
+/// removed, or the bindings are given the keys of another keyboard).
+pub struct KeyBindings<T = ()> {
+    modes: HashMap<String, Vec<BindingEntry<T>>>,
+    mode: String,
+    chord_timeout: Duration,
+    /// The start of a chord that has been typed so far.
+    pending: Vec<KeyCombo>,
+    /// The time of the last key of the pending chord.
+    pending_msec: u32,
+    /// Keys whose press was consumed, so their release is consumed as well.
+    /// Release bindings are run once their key is released.
+    consumed: Vec<(u32, Option<(String, usize)>)>
+}
+
+impl KeyCombo {
+    pub fn new(modifiers: KeyboardModifier, keysym: Key) -> Self {
+        KeyCombo { modifiers, keysym }
+    }
+}
+
+impl Binding {
+    /// Makes a binding that is triggered when `keysym` is pressed while
+    /// exactly `modifiers` are held.
+    ///
+    /// Caps Lock and Num Lock are ignored when matching.
+    pub fn new(modifiers: KeyboardModifier, keysym: Key) -> Self {
+        Binding {
+            chord: vec![KeyCombo::new(modifiers, keysym)],
+            release: false
+        }
+    }
+
+    /// Adds a key combo that has to follow the previous ones, which turns the
+    /// binding into a chord.
+    pub fn then(mut self, modifiers: KeyboardModifier, keysym: Key) -> Self {
+        self.chord.push(KeyCombo::new(modifiers, keysym));
+        self
+    }
+
+    /// Triggers the binding when its last key is released instead of when
+    /// it's pressed. The modifiers are still matched on the press.
+    pub fn on_release(mut self) -> Self {
+        self.release = true;
+        self
+    }
+
+    /// Gets the key combos of the binding, in the order they're pressed.
+    pub fn chord(&self) -> &[KeyCombo] {
+        &self.chord
+    }
+
+    /// Determines if the binding is triggered on release.
+    pub fn is_release(&self) -> bool {
+        self.release
+    }
+}
+
+impl<T> KeyBindings<T> {
+    pub fn new() -> Self {
+        KeyBindings {
+            modes: HashMap::new(),
+            mode: DEFAULT_MODE.into(),
+            chord_timeout: Duration::from_secs(1),
+            pending: Vec::new(),
+            pending_msec: 0,
+            consumed: Vec::new()
+        }
+    }
+
+    /// Runs `action` when `binding` is triggered in `mode`.
+    ///
+    /// If several bindings of a mode have the same keys, the first one wins.
+    /// A binding also wins over the chords that start with its keys, so
+    /// binding `A` makes the chord `A` then `B` unreachable.
+    pub fn bind<F>(&mut self, mode: &str, binding: Binding, action: F)
+        where F: FnMut(&mut Compositor<T>) + 'static
+    {
+        self.add(mode, binding, Action::Run(Box::new(action)))
+    }
+
+    /// Switches to `target` when `binding` is triggered in `mode`.
+    pub fn bind_mode(&mut self, mode: &str, binding: Binding, target: &str) {
+        self.add(mode, binding, Action::SwitchMode(target.into()))
+    }
+
+    fn add(&mut self, mode: &str, binding: Binding, action: Action<T>) {
+        self.modes
+            .entry(mode.into())
+            .or_insert_with(Vec::new)
+            .push(BindingEntry { binding, action })
+    }
+
+    /// Gets the name of the current mode.
+    pub fn mode(&self) -> &str {
+        &self.mode
+    }
+
+    /// Switches to another mode, which drops the chord typed so far and
+    /// forgets the keys that are held.
+    ///
+    /// A mode that has no bindings matches nothing.
+    pub fn set_mode(&mut self, mode: &str) {
+        self.mode = mode.into();
+        self.reset()
+    }
+
+    /// Drops the chord typed so far and forgets the keys that are held, so
+    /// their releases are no longer consumed or run release bindings.
+    ///
+    /// The mode stays the same.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.consumed.clear()
+    }
+
+    /// Sets how long the next key of a chord is waited for before the chord
+    /// is dropped. Defaults to one second.
+    pub fn set_chord_timeout(&mut self, timeout: Duration) {
+        self.chord_timeout = timeout
+    }
+
+    /// Matches a key event against the bindings of the current mode, running
+    /// the binding that is triggered by it.
+    ///
+    /// Returns true if the key was consumed by the bindings, in which case it
+    /// should not be sent to clients. This includes the start of a chord,
+    /// and the release and repeats of a consumed press.
+    pub fn handle_key(&mut self,
+                      compositor: &mut Compositor<T>,
+                      keyboard: &KeyboardHandle,
+                      key: &KeyEvent)
+                      -> bool {
+        let keycode = key.keycode();
+        if key.repeated() {
+            return self.consumed.iter().any(|&(code, _)| code == keycode)
+        }
+        match self.match_key(keyboard.get_modifiers(),
+                             &key.raw_keysyms(),
+                             key.state(),
+                             key.time_msec(),
+                             keycode) {
+            KeyMatch::Forward => false,
+            KeyMatch::Consumed => true,
+            KeyMatch::Run(mode, index) => {
+                self.run(compositor, &mode, index);
+                true
+            }
+        }
+    }
+
+    /// Matches a key that was pressed or released against the bindings of
+    /// the current mode, without running anything.
+    ///
+    /// Mode switches are done right away, the bindings that run an action
+    /// are returned to be run by the caller.
+    fn match_key(&mut self,
+                 modifiers: KeyboardModifier,
+                 keysyms: &[Key],
+                 state: wlr_key_state,
+                 time_msec: u32,
+                 keycode: u32)
+                 -> KeyMatch {
+        let consumed = self.consumed.iter().position(|&(code, _)| code == keycode);
+        match state {
+            WLR_KEY_RELEASED => {
+                let index = match consumed {
+                    Some(index) => index,
+                    None => return KeyMatch::Forward
+                };
+                match self.consumed.remove(index) {
+                    (_, Some((mode, index))) => self.trigger(mode, index),
+                    (_, None) => KeyMatch::Consumed
+                }
+            }
+            WLR_KEY_PRESSED => self.press(without_locks(modifiers), keysyms, time_msec, keycode)
+        }
+    }
+
+    fn press(&mut self,
+             modifiers: KeyboardModifier,
+             keysyms: &[Key],
+             time_msec: u32,
+             keycode: u32)
+             -> KeyMatch {
+        let timeout = duration_to_ms(self.chord_timeout) as u32;
+        if time_msec.wrapping_sub(self.pending_msec) > timeout {
+            self.pending.clear()
+        }
+        let mut prefix = None;
+        for &keysym in keysyms {
+            let mut chord = self.pending.clone();
+            chord.push(KeyCombo::new(modifiers, keysym));
+            let bindings = match self.modes.get(&self.mode) {
+                Some(bindings) => bindings,
+                None => break
+            };
+            if let Some(index) = bindings.iter().position(|entry| entry.binding.chord == chord) {
+                self.pending.clear();
+                let mode = self.mode.clone();
+                if bindings[index].binding.release {
+                    self.consumed.push((keycode, Some((mode, index))));
+                    return KeyMatch::Consumed
+                }
+                // NOTE Pushed after a mode switch, which forgets the consumed
+                // keys, so that the release of this key stays consumed.
+                let matched = self.trigger(mode, index);
+                self.consumed.push((keycode, None));
+                return matched
+            }
+            if prefix.is_none() &&
+               bindings.iter().any(|entry| entry.binding.chord.starts_with(&chord)) {
+                prefix = Some(chord)
+            }
+        }
+        match prefix {
+            Some(chord) => {
+                self.pending = chord;
+                self.pending_msec = time_msec;
+                self.consumed.push((keycode, None));
+                KeyMatch::Consumed
+            }
+            None => {
+                // Pressing a modifier is part of typing the next combo.
+                if !keysyms.iter().any(|&keysym| is_modifier(keysym)) {
+                    self.pending.clear()
+                }
+                KeyMatch::Forward
+            }
+        }
+    }
+
+    /// Triggers the binding at `index` in `mode`, switching the mode if it's
+    /// a mode binding.
+    fn trigger(&mut self, mode: String, index: usize) -> KeyMatch {
+        let target = match self.modes.get(&mode).and_then(|bindings| bindings.get(index)) {
+            Some(&BindingEntry { action: Action::SwitchMode(ref target), .. }) => target.clone(),
+            Some(_) => return KeyMatch::Run(mode, index),
+            None => return KeyMatch::Consumed
+        };
+        self.set_mode(&target);
+        KeyMatch::Consumed
+    }
+
+    fn run(&mut self, compositor: &mut Compositor<T>, mode: &str, index: usize) {
+        let entry = self.modes.get_mut(mode).and_then(|bindings| bindings.get_mut(index));
+        if let Some(&mut BindingEntry { action: Action::Run(ref mut action), .. }) = entry {
+            action(compositor)
+        }
+    }
+}
+
+impl<T> Default for KeyBindings<T> {
+    fn default() -> Self {
+        KeyBindings::new()
+    }
+}
+
+/// Determines if the keysym is one of a modifier key (e.g `Shift_L`).
+fn is_modifier(keysym: Key) -> bool {
+    // Shift_L to Hyper_R, and ISO_Lock to ISO_Last_Group_Lock
+    (0xffe1 <= keysym && keysym <= 0xffee) || (0xfe01 <= keysym && keysym <= 0xfe0f)
+}
+
+/// Where a drag is at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragState {
+    /// The pointer moved far enough from where the button was pressed.
+    Begin,
+    /// The pointer moved during the drag.
+    Motion,
+    /// The button was released, the drag is over.
+    End
+}
+
+/// A pointer button that is moved while held down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drag {
+    pub state: DragState,
+    pub button: u32,
+    /// Where the button was pressed.
+    pub start: (f64, f64),
+    /// Where the pointer is now.
+    pub position: (f64, f64)
+}
+
+enum ButtonTrigger<T> {
+    Press(Box<ButtonAction<T>>),
+    Release(Box<ButtonAction<T>>),
+    Click(Box<ButtonAction<T>>),
+    Drag(Box<DragAction<T>>)
+}
+
+struct ButtonEntry<T> {
+    modifiers: KeyboardModifier,
+    button: u32,
+    trigger: ButtonTrigger<T>
+}
+
+/// A binding that a button event or a motion triggers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ButtonMatch {
+    /// The button binding at the index runs with the position of the pointer.
+    Button(usize, (f64, f64)),
+    /// The drag binding at the index runs with the drag.
+    Drag(usize, Drag)
+}
+
+/// A consumed button that is held down.
+struct HeldButton {
+    /// The pointer the button belongs to.
+    device: *mut wlr_input_device,
+    modifiers: KeyboardModifier,
+    button: u32,
+    start: (f64, f64),
+    /// Whether the pointer moved further than the drag threshold.
+    moved: bool,
+    /// Whether a drag binding is running for the button.
+    dragging: bool
+}
+
+/// A set of pointer button bindings.
+///
+/// Positions are in layout coordinates, like `Cursor::coords`. The modifiers
+/// are those of the keyboard that was used last, see
+/// `Compositor::keyboard_modifiers`.
+pub struct PointerBindings<T = ()> {
+    bindings: Vec<ButtonEntry<T>>,
+    held: Vec<HeldButton>,
+    drag_threshold: f64
+}
+
+impl Drag {
+    /// Gets how far the pointer moved since the button was pressed.
+    pub fn delta(&self) -> (f64, f64) {
+        (self.position.0 - self.start.0, self.position.1 - self.start.1)
+    }
+}
+
+impl<T> PointerBindings<T> {
+    pub fn new() -> Self {
+        PointerBindings {
+            bindings: Vec::new(),
+            held: Vec::new(),
+            drag_threshold: 3.0
+        }
+    }
+
+    /// Runs `action` with the position of the pointer when `button` is
+    /// pressed while exactly `modifiers` are held.
+    ///
+    /// Caps Lock and Num Lock are ignored when matching.
+    pub fn bind_press<F>(&mut self, modifiers: KeyboardModifier, button: u32, action: F)
+        where F: FnMut(&mut Compositor<T>, (f64, f64)) + 'static
+    {
+        self.add(modifiers, button, ButtonTrigger::Press(Box::new(action)))
+    }
+
+    /// Runs `action` when `button` is released, if `modifiers` were held
+    /// when it was pressed.
+    pub fn bind_release<F>(&mut self, modifiers: KeyboardModifier, button: u32, action: F)
+        where F: FnMut(&mut Compositor<T>, (f64, f64)) + 'static
+    {
+        self.add(modifiers, button, ButtonTrigger::Release(Box::new(action)))
+    }
+
+    /// Runs `action` when `button` is released without being dragged, if
+    /// `modifiers` were held when it was pressed.
+    pub fn bind_click<F>(&mut self, modifiers: KeyboardModifier, button: u32, action: F)
+        where F: FnMut(&mut Compositor<T>, (f64, f64)) + 'static
+    {
+        self.add(modifiers, button, ButtonTrigger::Click(Box::new(action)))
+    }
+
+    /// Runs `action` for every step of a drag with `button`, if `modifiers`
+    /// were held when it was pressed.
+    pub fn bind_drag<F>(&mut self, modifiers: KeyboardModifier, button: u32, action: F)
+        where F: FnMut(&mut Compositor<T>, &Drag) + 'static
+    {
+        self.add(modifiers, button, ButtonTrigger::Drag(Box::new(action)))
+    }
+
+    fn add(&mut self, modifiers: KeyboardModifier, button: u32, trigger: ButtonTrigger<T>) {
+        self.bindings.push(ButtonEntry {
+                               modifiers,
+                               button,
+                               trigger
+                           })
+    }
+
+    /// Sets how far the pointer has to move with a button held before it
+    /// counts as a drag instead of a click. Defaults to 3.
+    pub fn set_drag_threshold(&mut self, threshold: f64) {
+        self.drag_threshold = threshold
+    }
+
+    /// Determines if a button is being dragged.
+    pub fn is_dragging(&self) -> bool {
+        self.held.iter().any(|held| held.dragging)
+    }
+
+    /// Matches a button event against the bindings, running the bindings
+    /// that are triggered by it.
+    ///
+    /// Returns true if the button was consumed by the bindings, in which case
+    /// it should not be sent to clients. The release of a consumed press is
+    /// consumed as well, if it comes from the same pointer.
+    pub fn handle_button(&mut self,
+                         compositor: &mut Compositor<T>,
+                         position: (f64, f64),
+                         event: &ButtonEvent)
+                         -> bool {
+        let device = unsafe { event.device().to_ptr() };
+        match self.match_button(device,
+                                compositor.keyboard_modifiers(),
+                                event.button(),
+                                event.state(),
+                                position) {
+            Some(matches) => {
+                self.run(compositor, &matches);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Moves the drags of the held buttons to the new position of the
+    /// pointer, call it whenever the pointer moves.
+    ///
+    /// Returns true if a button is being dragged, in which case the motion
+    /// should not be sent to clients.
+    pub fn handle_motion(&mut self, compositor: &mut Compositor<T>, position: (f64, f64)) -> bool {
+        let matches = self.match_motion(position);
+        self.run(compositor, &matches);
+        self.is_dragging()
+    }
+
+    /// Matches a button that was pressed or released against the bindings,
+    /// without running anything.
+    ///
+    /// Returns the triggered bindings, or `None` if the button isn't
+    /// consumed.
+    fn match_button(&mut self,
+                    device: *mut wlr_input_device,
+                    modifiers: KeyboardModifier,
+                    button: u32,
+                    state: wlr_button_state,
+                    position: (f64, f64))
+                    -> Option<Vec<ButtonMatch>> {
+        match state {
+            WLR_BUTTON_PRESSED => {
+                let modifiers = without_locks(modifiers);
+                let mut consumed = false;
+                let mut matches = Vec::new();
+                for (index, entry) in self.bindings.iter().enumerate() {
+                    if entry.modifiers != modifiers || entry.button != button {
+                        continue
+                    }
+                    consumed = true;
+                    if let ButtonTrigger::Press(_) = entry.trigger {
+                        matches.push(ButtonMatch::Button(index, position))
+                    }
+                }
+                if !consumed {
+                    return None
+                }
+                self.held.push(HeldButton {
+                                   device,
+                                   modifiers,
+                                   button,
+                                   start: position,
+                                   moved: false,
+                                   dragging: false
+                               });
+                Some(matches)
+            }
+            WLR_BUTTON_RELEASED => {
+                let held = self.held
+                    .iter()
+                    .position(|held| held.device == device && held.button == button);
+                let held = match held {
+                    Some(index) => self.held.remove(index),
+                    None => return None
+                };
+                let mut matches = Vec::new();
+                for (index, entry) in self.bindings.iter().enumerate() {
+                    if entry.modifiers != held.modifiers || entry.button != button {
+                        continue
+                    }
+                    match entry.trigger {
+                        ButtonTrigger::Release(_) => {
+                            matches.push(ButtonMatch::Button(index, position))
+                        }
+                        ButtonTrigger::Click(_) if !held.moved => {
+                            matches.push(ButtonMatch::Button(index, position))
+                        }
+                        ButtonTrigger::Drag(_) if held.dragging => {
+                            let drag = Drag {
+                                state: DragState::End,
+                                button,
+                                start: held.start,
+                                position
+                            };
+                            matches.push(ButtonMatch::Drag(index, drag))
+                        }
+                        _ => {}
+                    }
+                }
+                Some(matches)
+            }
+        }
+    }
+
+    /// Matches a motion of the pointer against the held buttons, without
+    /// running anything.
+    fn match_motion(&mut self, position: (f64, f64)) -> Vec<ButtonMatch> {
+        let threshold = self.drag_threshold;
+        let mut matches = Vec::new();
+        for held in &mut self.held {
+            if !held.moved {
+                let (delta_x, delta_y) = (position.0 - held.start.0, position.1 - held.start.1);
+                if (delta_x * delta_x + delta_y * delta_y).sqrt() < threshold {
+                    continue
+                }
+                held.moved = true
+            }
+            let state = if held.dragging {
+                DragState::Motion
+            } else {
+                DragState::Begin
+            };
+            let drag = Drag {
+                state,
+                button: held.button,
+                start: held.start,
+                position
+            };
+            for (index, entry) in self.bindings.iter().enumerate() {
+                if entry.modifiers != held.modifiers || entry.button != held.button {
+                    continue
+                }
+                if let ButtonTrigger::Drag(_) = entry.trigger {
+                    held.dragging = true;
+                    matches.push(ButtonMatch::Drag(index, drag))
+                }
+            }
+        }
+        matches
+    }
+
+    fn run(&mut self, compositor: &mut Compositor<T>, matches: &[ButtonMatch]) {
+        for matched in matches {
+            match *matched {
+                ButtonMatch::Button(index, position) => {
+                    match self.bindings[index].trigger {
+                        ButtonTrigger::Press(ref mut action) |
+                        ButtonTrigger::Release(ref mut action) |
+                        ButtonTrigger::Click(ref mut action) => action(compositor, position),
+                        ButtonTrigger::Drag(_) => {}
+                    }
+                }
+                ButtonMatch::Drag(index, ref drag) => {
+                    if let ButtonTrigger::Drag(ref mut action) = self.bindings[index].trigger {
+                        action(compositor, drag)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> Default for PointerBindings<T> {
+    fn default() -> Self {
+        PointerBindings::new()
+    }
+}
+
+/// Removes the lock modifiers, which shouldn't change what a binding does.
+fn without_locks(modifiers: KeyboardModifier) -> KeyboardModifier {
+    modifiers - (KeyboardModifier::WLR_MODIFIER_CAPS | KeyboardModifier::WLR_MODIFIER_MOD2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xkbcommon::xkb::keysyms::*;
+
+    const BTN_LEFT: u32 = 0x110;
+
+    /// Only compared, never dereferenced.
+    fn mouse() -> *mut wlr_input_device {
+        1 as *mut _
+    }
+
+    fn touchpad() -> *mut wlr_input_device {
+        2 as *mut _
+    }
+
+    fn key(bindings: &mut KeyBindings,
+           modifiers: KeyboardModifier,
+           keysym: Key,
+           state: wlr_key_state,
+           time_msec: u32)
+           -> KeyMatch {
+        // The keysym doubles as the keycode.
+        bindings.match_key(modifiers, &[keysym], state, time_msec, keysym)
+    }
+
+    #[test]
+    fn chord() {
+        let ctrl = KeyboardModifier::WLR_MODIFIER_CTRL;
+        let mut bindings: KeyBindings = KeyBindings::new();
+        bindings.bind(DEFAULT_MODE, Binding::new(ctrl, KEY_x).then(ctrl, KEY_c), |_| {});
+        assert_eq!(key(&mut bindings, ctrl, KEY_x, WLR_KEY_PRESSED, 0), KeyMatch::Consumed);
+        assert_eq!(key(&mut bindings, ctrl, KEY_x, WLR_KEY_RELEASED, 10), KeyMatch::Consumed);
+        assert_eq!(key(&mut bindings, ctrl, KEY_c, WLR_KEY_PRESSED, 500),
+                   KeyMatch::Run(DEFAULT_MODE.into(), 0));
+        assert_eq!(key(&mut bindings, ctrl, KEY_c, WLR_KEY_RELEASED, 510), KeyMatch::Consumed);
+        // The chord starts over.
+        assert_eq!(key(&mut bindings, ctrl, KEY_c, WLR_KEY_PRESSED, 600), KeyMatch::Forward);
+    }
+
+    #[test]
+    fn chord_timeout() {
+        let ctrl = KeyboardModifier::WLR_MODIFIER_CTRL;
+        let mut bindings: KeyBindings = KeyBindings::new();
+        bindings.bind(DEFAULT_MODE, Binding::new(ctrl, KEY_x).then(ctrl, KEY_c), |_| {});
+        bindings.set_chord_timeout(Duration::from_millis(100));
+        assert_eq!(key(&mut bindings, ctrl, KEY_x, WLR_KEY_PRESSED, 0), KeyMatch::Consumed);
+        assert_eq!(key(&mut bindings, ctrl, KEY_c, WLR_KEY_PRESSED, 101), KeyMatch::Forward);
+    }
+
+    #[test]
+    fn mode_switch() {
+        let logo = KeyboardModifier::WLR_MODIFIER_LOGO;
+        let none = KeyboardModifier::empty();
+        let mut bindings: KeyBindings = KeyBindings::new();
+        bindings.bind_mode(DEFAULT_MODE, Binding::new(logo, KEY_r), "resize");
+        bindings.bind("resize", Binding::new(none, KEY_Escape), |_| {});
+        assert_eq!(key(&mut bindings, none, KEY_Escape, WLR_KEY_PRESSED, 0), KeyMatch::Forward);
+        assert_eq!(key(&mut bindings, logo, KEY_r, WLR_KEY_PRESSED, 10), KeyMatch::Consumed);
+        assert_eq!(bindings.mode(), "resize");
+        assert_eq!(key(&mut bindings, logo, KEY_r, WLR_KEY_RELEASED, 20), KeyMatch::Consumed);
+        assert_eq!(key(&mut bindings, none, KEY_Escape, WLR_KEY_PRESSED, 30),
+                   KeyMatch::Run("resize".into(), 0));
+    }
+
+    #[test]
+    fn release_binding() {
+        let none = KeyboardModifier::empty();
+        let mut bindings: KeyBindings = KeyBindings::new();
+        bindings.bind(DEFAULT_MODE, Binding::new(none, KEY_a).on_release(), |_| {});
+        // Caps Lock doesn't change the binding.
+        let caps = KeyboardModifier::WLR_MODIFIER_CAPS;
+        assert_eq!(key(&mut bindings, caps, KEY_a, WLR_KEY_PRESSED, 0), KeyMatch::Consumed);
+        assert_eq!(key(&mut bindings, caps, KEY_a, WLR_KEY_RELEASED, 10),
+                   KeyMatch::Run(DEFAULT_MODE.into(), 0));
+        assert_eq!(key(&mut bindings, none, KEY_a, WLR_KEY_RELEASED, 20), KeyMatch::Forward);
+    }
+
+    #[test]
+    fn reset_forgets_held_keys() {
+        let none = KeyboardModifier::empty();
+        let mut bindings: KeyBindings = KeyBindings::new();
+        bindings.bind(DEFAULT_MODE, Binding::new(none, KEY_a), |_| {});
+        bindings.bind(DEFAULT_MODE, Binding::new(none, KEY_x).on_release(), |_| {});
+        assert_eq!(key(&mut bindings, none, KEY_a, WLR_KEY_PRESSED, 0),
+                   KeyMatch::Run(DEFAULT_MODE.into(), 0));
+        assert_eq!(key(&mut bindings, none, KEY_x, WLR_KEY_PRESSED, 10), KeyMatch::Consumed);
+        // The keyboard went away before the keys were released.
+        bindings.reset();
+        assert_eq!(key(&mut bindings, none, KEY_a, WLR_KEY_RELEASED, 20), KeyMatch::Forward);
+        assert_eq!(key(&mut bindings, none, KEY_x, WLR_KEY_RELEASED, 30), KeyMatch::Forward);
+    }
+
+    #[test]
+    fn mode_switch_forgets_held_keys() {
+        let none = KeyboardModifier::empty();
+        let mut bindings: KeyBindings = KeyBindings::new();
+        bindings.bind(DEFAULT_MODE, Binding::new(none, KEY_a), |_| {});
+        bindings.bind_mode(DEFAULT_MODE, Binding::new(none, KEY_r), "resize");
+        key(&mut bindings, none, KEY_a, WLR_KEY_PRESSED, 0);
+        assert_eq!(key(&mut bindings, none, KEY_r, WLR_KEY_PRESSED, 10), KeyMatch::Consumed);
+        // The key that switched modes is still consumed, the one before it
+        // isn't.
+        assert_eq!(key(&mut bindings, none, KEY_r, WLR_KEY_RELEASED, 20), KeyMatch::Consumed);
+        assert_eq!(key(&mut bindings, none, KEY_a, WLR_KEY_RELEASED, 30), KeyMatch::Forward);
+    }
+
+    #[test]
+    fn binding_shadows_chord() {
+        let none = KeyboardModifier::empty();
+        let mut bindings: KeyBindings = KeyBindings::new();
+        bindings.bind(DEFAULT_MODE, Binding::new(none, KEY_a), |_| {});
+        bindings.bind(DEFAULT_MODE, Binding::new(none, KEY_a).then(none, KEY_x), |_| {});
+        assert_eq!(key(&mut bindings, none, KEY_a, WLR_KEY_PRESSED, 0),
+                   KeyMatch::Run(DEFAULT_MODE.into(), 0));
+        assert_eq!(key(&mut bindings, none, KEY_x, WLR_KEY_PRESSED, 10), KeyMatch::Forward);
+    }
+
+    fn drag_bindings() -> PointerBindings {
+        let logo = KeyboardModifier::WLR_MODIFIER_LOGO;
+        let mut bindings: PointerBindings = PointerBindings::new();
+        bindings.bind_click(logo, BTN_LEFT, |_, _| {});
+        bindings.bind_drag(logo, BTN_LEFT, |_, _| {});
+        bindings
+    }
+
+    fn drag(state: DragState, position: (f64, f64)) -> ButtonMatch {
+        ButtonMatch::Drag(1,
+                          Drag {
+                              state,
+                              button: BTN_LEFT,
+                              start: (0.0, 0.0),
+                              position
+                          })
+    }
+
+    #[test]
+    fn click_below_threshold() {
+        let logo = KeyboardModifier::WLR_MODIFIER_LOGO;
+        let mut bindings = drag_bindings();
+        assert_eq!(bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_PRESSED, (0.0, 0.0)),
+                   Some(vec![]));
+        assert_eq!(bindings.match_motion((2.9, 0.0)), vec![]);
+        assert!(!bindings.is_dragging());
+        assert_eq!(bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_RELEASED, (2.9, 0.0)),
+                   Some(vec![ButtonMatch::Button(0, (2.9, 0.0))]));
+    }
+
+    #[test]
+    fn drag_at_threshold() {
+        let logo = KeyboardModifier::WLR_MODIFIER_LOGO;
+        let mut bindings = drag_bindings();
+        bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_PRESSED, (0.0, 0.0));
+        assert_eq!(bindings.match_motion((3.0, 0.0)),
+                   vec![drag(DragState::Begin, (3.0, 0.0))]);
+        assert!(bindings.is_dragging());
+        // Moving back within the threshold doesn't turn it into a click.
+        assert_eq!(bindings.match_motion((1.0, 0.0)),
+                   vec![drag(DragState::Motion, (1.0, 0.0))]);
+        assert_eq!(bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_RELEASED, (1.0, 0.0)),
+                   Some(vec![drag(DragState::End, (1.0, 0.0))]));
+        assert!(!bindings.is_dragging());
+    }
+
+    #[test]
+    fn drag_end_only_after_begin() {
+        let logo = KeyboardModifier::WLR_MODIFIER_LOGO;
+        let mut bindings: PointerBindings = PointerBindings::new();
+        bindings.bind_drag(logo, BTN_LEFT, |_, _| {});
+        bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_PRESSED, (0.0, 0.0));
+        assert_eq!(bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_RELEASED, (0.0, 0.0)),
+                   Some(vec![]));
+    }
+
+    #[test]
+    fn unbound_button() {
+        let mut bindings = drag_bindings();
+        let none = KeyboardModifier::empty();
+        assert_eq!(bindings.match_button(mouse(), none, BTN_LEFT, WLR_BUTTON_PRESSED, (0.0, 0.0)),
+                   None);
+        assert_eq!(bindings.match_motion((10.0, 0.0)), vec![]);
+        assert_eq!(bindings.match_button(mouse(), none, BTN_LEFT, WLR_BUTTON_RELEASED, (10.0, 0.0)),
+                   None);
+    }
+
+    #[test]
+    fn release_of_another_pointer() {
+        let logo = KeyboardModifier::WLR_MODIFIER_LOGO;
+        let mut bindings = drag_bindings();
+        bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_PRESSED, (0.0, 0.0));
+        let released = bindings.match_button(touchpad(),
+                                             logo,
+                                             BTN_LEFT,
+                                             WLR_BUTTON_RELEASED,
+                                             (0.0, 0.0));
+        assert_eq!(released, None);
+        assert_eq!(bindings.match_button(mouse(), logo, BTN_LEFT, WLR_BUTTON_RELEASED, (0.0, 0.0)),
+                   Some(vec![ButtonMatch::Button(0, (0.0, 0.0))]));
+    }
+}
@@ -1,35 +1,191 @@
 //! Main entry point to the library.
 //! See examples for documentation on how to use this struct.
 
+use libc::{self, c_void};
+
+use std::{env, error, fmt, io, thread};
 use std::any::Any;
-use std::cell::UnsafeCell;
-use std::env;
-use std::ffi::CStr;
+use std::cell::Cell;
+use std::ffi::{CStr, CString, OsString};
+use std::os::unix::io::RawFd;
+use std::os::unix::process::CommandExt;
+use std::panic;
+use std::process::{Child, Command};
+use std::ptr;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
 
+use backend::Backend;
+use event_loop::{EventLoop, duration_to_ms};
 use extensions::server_decoration::ServerDecorationManager;
-use manager::{InputManager, InputManagerHandler, OutputManager, OutputManagerHandler};
+use ffi::{wl_display_destroy_clients, wl_display_set_global_filter, wl_global_get_interface};
+use logging::{self, LogVerbosity};
+use manager::{ClientManager, ClientManagerHandler, InputManager, InputManagerHandler,
+              OutputManager, OutputManagerHandler};
 use render::GLES2;
+use types::{Client, ClientInfo, KeyRepeat, KeyboardModifier, Keymap, KeymapError, KeymapNames};
+use utils::{handle_unwind, take_panic};
 
-use wayland_sys::server::{WAYLAND_SERVER_HANDLE, wl_display, wl_event_loop};
+use wayland_sys::server::{WAYLAND_SERVER_HANDLE, wl_client, wl_display, wl_global};
 use wayland_sys::server::signal::wl_signal_add;
-use wlroots_sys::{wlr_backend, wlr_backend_autocreate, wlr_backend_destroy, wlr_backend_start};
+use wlroots_sys::{wlr_backend, wlr_backend_destroy, wlr_backend_start, wlr_keyboard,
+                  wlr_keyboard_get_modifiers};
+
+/// Errors that can occur while building or running a `Compositor`.
+///
+/// Each variant names the stage of the setup that failed.
+#[derive(Debug)]
+pub enum CompositorError {
+    /// The chosen backend (e.g Wayland, X11, or DRM) could not be created.
+    BackendCreation,
+    /// The Wayland socket for clients could not be opened.
+    Socket,
+    /// The backend was created, but it could not be started.
+    BackendStart,
+    /// A renderer was requested, but it could not be created.
+    Renderer,
+    /// Polling or dispatching the event loop failed.
+    Dispatch,
+    /// A handler panicked while it was called from C.
+    /// Contains the panic payload, which can be passed to
+    /// `std::panic::resume_unwind` to continue the panic.
+    HandlerPanicked(Box<Any + Send>)
+}
+
+impl fmt::Display for CompositorError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompositorError::HandlerPanicked(ref payload) => {
+                let message = payload.downcast_ref::<&str>()
+                    .map(|message| *message)
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+                if let Some(message) = message {
+                    return write!(formatter, "A handler panicked: {}", message)
+                }
+            }
+            _ => {}
+        }
+        write!(formatter, "{}", error::Error::description(self))
+    }
+}
+
+impl error::Error for CompositorError {
+    fn description(&self) -> &str {
+        use self::CompositorError::*;
+        match *self {
+            BackendCreation => "Could not create backend",
+            Socket => "Unable to open wayland socket",
+            BackendStart => "Failed to start backend",
+            Renderer => "Could not create renderer",
+            Dispatch => "Failed to dispatch events",
+            HandlerPanicked(_) => "A handler panicked"
+        }
+    }
+}
+
+/// How the name of the socket is exported to the environment, so that
+/// processes started by the compositor know where to connect to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayExport {
+    /// Leave the environment alone.
+    None,
+    /// Set `WAYLAND_DISPLAY`, which is what clients read.
+    WaylandDisplay,
+    /// Set the given environment variable instead.
+    Variable(String)
+}
 
-/// Global compositor pointer, used to refer to the compositor state unsafely.
-pub static mut COMPOSITOR_PTR: *mut Compositor = 0 as *mut _;
+/// Where the socket that clients connect to comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SocketSource {
+    /// The first free `wayland-N` name.
+    Auto,
+    Name(String),
+    /// An already listening socket, e.g from socket activation.
+    Fd(RawFd)
+}
+
+/// Decides if a client may see and bind a global, given the name of its
+/// interface (e.g `wl_seat`).
+type GlobalFilterFn = Fn(&ClientInfo, &str) -> bool;
+
+/// The data given to the global filter of the display.
+struct GlobalFilter<T> {
+    filter: Box<GlobalFilterFn>,
+    client_manager: *mut ClientManager<T>
+}
 
 pub struct CompositorBuilder {
+    backend: Backend,
     gles2: bool,
-    server_decoration_manager: bool
+    server_decoration_manager: bool,
+    socket: SocketSource,
+    display_export: DisplayExport,
+    log_verbosity: Option<LogVerbosity>,
+    global_filter: Option<Box<GlobalFilterFn>>,
+    keymap: Option<Keymap>
 }
 
 impl CompositorBuilder {
     pub fn new() -> Self {
         CompositorBuilder {
+            backend: Backend::Auto,
             gles2: false,
-            server_decoration_manager: false
+            server_decoration_manager: false,
+            socket: SocketSource::Auto,
+            display_export: DisplayExport::None,
+            log_verbosity: None,
+            global_filter: None,
+            keymap: None
         }
     }
 
+    /// Chooses the backend the compositor runs on.
+    ///
+    /// By default the backend is automatically chosen by wlroots.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Opens the socket with the given name (e.g `wayland-1`) in
+    /// `XDG_RUNTIME_DIR`, instead of picking the first free one.
+    pub fn socket_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.socket = SocketSource::Name(name.into());
+        self
+    }
+
+    /// Uses an already listening socket instead of opening one, e.g one
+    /// that was passed in by socket activation.
+    ///
+    /// The compositor takes ownership of the file descriptor.
+    /// Since the socket has no name, nothing is exported to the environment.
+    pub fn socket_fd(mut self, fd: RawFd) -> Self {
+        self.socket = SocketSource::Fd(fd);
+        self
+    }
+
+    /// Chooses how the name of the socket is exported to the environment.
+    ///
+    /// The previous value of the variable is restored when the compositor
+    /// is dropped.
+    ///
+    /// By default nothing is exported, `spawn` and `spawn_connected` tell
+    /// the programs they start where to connect to either way.
+    pub fn display_export(mut self, display_export: DisplayExport) -> Self {
+        self.display_export = display_export;
+        self
+    }
+
+    /// Sends the log messages of wlroots to the `log` crate, with the given
+    /// verbosity. See the `logging` module for details.
+    ///
+    /// By default wlroots logs to stderr.
+    pub fn log_verbosity(mut self, verbosity: LogVerbosity) -> Self {
+        self.log_verbosity = Some(verbosity);
+        self
+    }
+
     pub fn gles2(mut self, gles2_renderer: bool) -> Self {
         self.gles2 = gles2_renderer;
         self
@@ -40,31 +196,95 @@ impl CompositorBuilder {
         self
     }
 
+    /// Sets the keymap every keyboard gets when it is added.
+    ///
+    /// By default the keymap is compiled from the `XKB_DEFAULT_*`
+    /// environment variables when the first keyboard is added, so a
+    /// compositor without keyboards (e.g a headless one) doesn't need the
+    /// XKB data.
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = Some(keymap);
+        self
+    }
+
+    /// Sets the policy that decides which globals each client may see and
+    /// bind, given the client and the name of the global's interface
+    /// (e.g `org_kde_kwin_server_decoration_manager`).
+    ///
+    /// Use this to limit privileged protocols, like screenshots or gamma
+    /// control, to trusted clients (e.g by checking `ClientInfo::pid` or
+    /// `ClientInfo::uid`).
+    ///
+    /// The filter runs while the display dispatches the client, so it only
+    /// gets a `ClientInfo` that can't disconnect it.
+    ///
+    /// By default every client can see every global.
+    pub fn global_filter<F>(mut self, filter: F) -> Self
+        where F: Fn(&ClientInfo, &str) -> bool + 'static
+    {
+        self.global_filter = Some(Box::new(filter));
+        self
+    }
+
     /// Makes a new compositor that handles the setup of the graphical backend
     /// (e.g, Wayland, X11, or DRM).
     ///
     /// Also automatically opens the socket for clients to communicate to the
     /// compositor with.
-    pub fn build_auto<T: Any + 'static>(self,
-                                        data: T,
-                                        input_manager_handler: Box<InputManagerHandler>,
-                                        output_manager_handler: Box<OutputManagerHandler>)
-                                        -> Compositor {
+    ///
+    /// # Panics
+    /// Panics if the compositor could not be built.
+    /// Use `build` to handle the error instead.
+    pub fn build_auto<T>(self,
+                         data: T,
+                         input_manager_handler: Box<InputManagerHandler<T>>,
+                         output_manager_handler: Box<OutputManagerHandler<T>>)
+                         -> Compositor<T> {
+        match self.build(data, input_manager_handler, output_manager_handler) {
+            Ok(compositor) => compositor,
+            Err(err) => panic!("Could not build compositor: {}", err)
+        }
+    }
+
+    /// Makes a new compositor that handles the setup of the chosen graphical
+    /// backend (e.g, Wayland, X11, or DRM).
+    ///
+    /// Also automatically opens the socket for clients to communicate to the
+    /// compositor with.
+    ///
+    /// The `data` is the global state of the compositor, which is available
+    /// to every handler through `Compositor::data`.
+    ///
+    /// If any step of the setup fails everything that was created up to that
+    /// point is destroyed and the failing stage is returned.
+    pub fn build<T>(mut self,
+                    data: T,
+                    input_manager_handler: Box<InputManagerHandler<T>>,
+                    output_manager_handler: Box<OutputManagerHandler<T>>)
+                    -> Result<Compositor<T>, CompositorError> {
+        if let Some(verbosity) = self.log_verbosity {
+            logging::init(verbosity)
+        }
+        let keymap = self.keymap.take();
         unsafe {
             let display = ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_create,) as
                 *mut wl_display;
-            let event_loop =
-                ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_get_event_loop, display);
-            let backend = wlr_backend_autocreate(display as *mut _);
+            let backend = self.backend.create(display);
             if backend.is_null() {
-                // NOTE Rationale for panicking:
-                // * Won't be in C land just yet, so it's safe to panic
-                // * Can always be returned in a Result instead, but for now
-                //   if you auto create it's assumed you can't recover.
-                panic!("Could not auto-create backend");
+                wlr_log!(L_ERROR, "Could not create backend {:?}", self.backend);
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_destroy, display);
+                return Err(CompositorError::BackendCreation)
             }
-            let mut input_manager = InputManager::new((vec![], input_manager_handler));
-            let mut output_manager = OutputManager::new((vec![], output_manager_handler));
+            let handle = CompositorHandle::new();
+            let event_loop =
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_get_event_loop, display);
+            let event_loop = EventLoop::new(event_loop, handle.clone());
+            let mut input_manager = InputManager::new((vec![],
+                                                       input_manager_handler,
+                                                       handle.clone()));
+            let mut output_manager = OutputManager::new((vec![],
+                                                         output_manager_handler,
+                                                         handle.clone()));
             wl_signal_add(&mut (*backend).events.input_add as *mut _ as _,
                           input_manager.add_listener() as *mut _ as _);
             wl_signal_add(&mut (*backend).events.input_remove as *mut _ as _,
@@ -80,93 +300,585 @@ impl CompositorBuilder {
                 None
             };
             let gles2 = if self.gles2 {
-                GLES2::new(backend)
+                match GLES2::new(backend) {
+                    Some(gles2) => Some(gles2),
+                    None => {
+                        drop(server_decoration_manager);
+                        destroy_backend(backend, &mut input_manager, &mut output_manager);
+                        ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_destroy, display);
+                        return Err(CompositorError::Renderer)
+                    }
+                }
             } else {
                 None
             };
 
-            let socket = ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_add_socket_auto, display);
-            if socket.is_null() {
-                // NOTE Rationale for panicking:
-                // * Won't be in C land just yet, so it's safe to panic
-                // * Can always be returned in a Result instead, but for now
-                //   if you auto create it's assumed you can't recover.
-                panic!("Unable to open wayland socket");
+            let socket_name = match self.socket.open(display) {
+                Ok(socket_name) => socket_name,
+                Err(()) => {
+                    wlr_log!(L_ERROR, "Unable to open wayland socket {:?}", self.socket);
+                    drop(gles2);
+                    drop(server_decoration_manager);
+                    destroy_backend(backend, &mut input_manager, &mut output_manager);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_destroy, display);
+                    return Err(CompositorError::Socket)
+                }
+            };
+            if let Some(ref socket_name) = socket_name {
+                wlr_log!(L_DEBUG,
+                         "Running compositor on wayland display {}",
+                         socket_name);
             }
-            let socket_name = CStr::from_ptr(socket).to_string_lossy().into_owned();
-            wlr_log!(L_DEBUG,
-                     "Running compositor on wayland display {}",
-                     socket_name);
-            env::set_var("_WAYLAND_DISPLAY", socket_name);
-            Compositor {
-                data: Box::new(data),
+            let exported_var = socket_name.as_ref().and_then(|socket_name| {
+                let var = match self.display_export {
+                    DisplayExport::None => return None,
+                    DisplayExport::WaylandDisplay => "WAYLAND_DISPLAY".to_string(),
+                    DisplayExport::Variable(ref var) => var.clone()
+                };
+                let previous = env::var_os(&var);
+                env::set_var(&var, socket_name);
+                Some((var, previous))
+            });
+            let mut client_manager = ClientManager::new((vec![], None, handle.clone()));
+            ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                          wl_display_add_client_created_listener,
+                          display,
+                          client_manager.created_listener() as *mut _ as _);
+            let global_filter = self.global_filter.map(|filter| {
+                let mut global_filter = Box::new(GlobalFilter {
+                                                     filter,
+                                                     client_manager: &mut *client_manager
+                                                 });
+                wl_display_set_global_filter(display,
+                                             global_filter_notify::<T>,
+                                             &mut *global_filter as *mut _ as *mut c_void);
+                global_filter
+            });
+            Ok(Compositor {
+                data,
                 input_manager,
                 output_manager,
+                client_manager,
+                global_filter,
+                keymap,
+                key_repeats: Vec::new(),
+                active_keyboard: ptr::null_mut(),
                 backend,
                 display,
                 event_loop,
                 server_decoration_manager,
-                gles2
+                gles2,
+                handle,
+                socket_name,
+                exported_var,
+                started: false,
+                running: false
+            })
+        }
+    }
+}
+
+impl SocketSource {
+    /// Adds the socket to the display, returning its name if it has one.
+    unsafe fn open(&self, display: *mut wl_display) -> Result<Option<String>, ()> {
+        match *self {
+            SocketSource::Auto => {
+                let socket = ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                           wl_display_add_socket_auto,
+                                           display);
+                if socket.is_null() {
+                    return Err(())
+                }
+                Ok(Some(CStr::from_ptr(socket).to_string_lossy().into_owned()))
+            }
+            SocketSource::Name(ref name) => {
+                let c_name = match CString::new(name.as_str()) {
+                    Ok(c_name) => c_name,
+                    Err(_) => {
+                        wlr_log!(L_ERROR, "Socket name {:?} contains a NUL byte", name);
+                        return Err(())
+                    }
+                };
+                match ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                    wl_display_add_socket,
+                                    display,
+                                    c_name.as_ptr()) {
+                    0 => Ok(Some(name.clone())),
+                    _ => Err(())
+                }
+            }
+            SocketSource::Fd(fd) => {
+                match ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_add_socket_fd, display, fd) {
+                    0 => Ok(None),
+                    _ => Err(())
+                }
             }
         }
     }
 }
 
+/// Asks the filter of the compositor if a client can see a global.
+unsafe extern "C" fn global_filter_notify<T>(client: *const wl_client,
+                                             global: *const wl_global,
+                                             data: *mut c_void)
+                                             -> bool {
+    let global_filter = &*(data as *mut GlobalFilter<T>);
+    let client = client as *mut wl_client;
+    // NOTE Clients are tracked from the moment they connect, the fallback is
+    // only there to be safe.
+    let client = (*global_filter.client_manager)
+        .find(client)
+        .unwrap_or_else(|| Client::from_ptr(client));
+    let client = ClientInfo::new(client);
+    let interface = CStr::from_ptr((*wl_global_get_interface(global)).name).to_string_lossy();
+    handle_unwind(|| (global_filter.filter)(&client, &interface)).unwrap_or(false)
+}
+
+/// Kills a spawned program that could not be connected, and waits for it
+/// so that it isn't left behind as a zombie.
+fn kill_child(mut child: Child) {
+    // NOTE Fails if the program already exited, it still has to be reaped.
+    let _ = child.kill();
+    if let Err(err) = child.wait() {
+        wlr_log!(L_ERROR, "Could not reap spawned program: {}", err);
+    }
+}
+
+/// Unhooks the managers from the backend and then destroys it.
+///
+/// Only used when building the compositor fails, before any devices could
+/// have been announced to the managers.
+unsafe fn destroy_backend<T>(backend: *mut wlr_backend,
+                             input_manager: &mut InputManager<T>,
+                             output_manager: &mut OutputManager<T>) {
+    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                  wl_list_remove,
+                  &mut (*input_manager.add_listener()).link as *mut _ as _);
+    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                  wl_list_remove,
+                  &mut (*input_manager.remove_listener()).link as *mut _ as _);
+    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                  wl_list_remove,
+                  &mut (*output_manager.add_listener()).link as *mut _ as _);
+    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                  wl_list_remove,
+                  &mut (*output_manager.remove_listener()).link as *mut _ as _);
+    wlr_backend_destroy(backend);
+}
+
+/// Shared pointer to the `Compositor`, given to every listener that calls
+/// back into user code.
+///
+/// The pointer is only set while the compositor is dispatching events,
+/// because that is the only time it's guaranteed not to move.
+pub(crate) struct CompositorHandle<T>(Rc<Cell<*mut Compositor<T>>>);
+
+impl<T> Clone for CompositorHandle<T> {
+    fn clone(&self) -> Self {
+        CompositorHandle(self.0.clone())
+    }
+}
+
+impl<T> CompositorHandle<T> {
+    fn new() -> Self {
+        CompositorHandle(Rc::new(Cell::new(ptr::null_mut())))
+    }
+
+    fn set(&self, compositor: *mut Compositor<T>) {
+        self.0.set(compositor)
+    }
+
+    /// Sets the compositor, returning the one that was set before.
+    fn replace(&self, compositor: *mut Compositor<T>) -> *mut Compositor<T> {
+        self.0.replace(compositor)
+    }
+
+    /// Gets the compositor, if it is currently dispatching events.
+    ///
+    /// # Unsafety
+    /// The returned reference must not outlive the callback it is used in.
+    pub(crate) unsafe fn get<'compositor>(&self) -> Option<&'compositor mut Compositor<T>> {
+        let compositor = self.0.get();
+        if compositor.is_null() {
+            wlr_log!(L_ERROR, "Compositor callback triggered while not dispatching events");
+            None
+        } else {
+            Some(&mut *compositor)
+        }
+    }
+}
+
 #[allow(dead_code)]
-pub struct Compositor {
-    pub data: Box<Any>,
-    input_manager: Box<InputManager>,
-    output_manager: Box<OutputManager>,
+pub struct Compositor<T = ()> {
+    /// The global state of the compositor, given to `CompositorBuilder::build`.
+    pub data: T,
+    input_manager: Box<InputManager<T>>,
+    output_manager: Box<OutputManager<T>>,
+    client_manager: Box<ClientManager<T>>,
+    /// Kept alive for as long as the display uses it.
+    global_filter: Option<Box<GlobalFilter<T>>>,
+    /// Compiled the first time it's needed, unless one was given.
+    keymap: Option<Keymap>,
+    /// The key repeat of every keyboard, shared with them so that it can be
+    /// cancelled while a keyboard is borrowed (e.g from `on_key`).
+    key_repeats: Vec<Weak<KeyRepeat>>,
+    /// The keyboard that was used last, which stands in for the active
+    /// keyboard of a seat. Null if there's none.
+    active_keyboard: *mut wlr_keyboard,
     backend: *mut wlr_backend,
     display: *mut wl_display,
-    event_loop: *mut wl_event_loop,
+    event_loop: EventLoop<T>,
     pub server_decoration_manager: Option<ServerDecorationManager>,
-    pub gles2: Option<GLES2>
+    pub gles2: Option<GLES2>,
+    handle: CompositorHandle<T>,
+    socket_name: Option<String>,
+    /// The environment variable the socket name was exported to, with the
+    /// value it had before.
+    exported_var: Option<(String, Option<OsString>)>,
+    /// Whether the backend has been started.
+    started: bool,
+    /// Whether `run` should keep dispatching events.
+    running: bool
 }
 
-impl Compositor {
+impl<T> Compositor<T> {
     /// Enters the wayland event loop. Won't return until the compositor is
-    /// shut off
-    pub fn run(self) {
+    /// shut off.
+    ///
+    /// Once the event loop exits everything the compositor wraps is
+    /// destroyed before this returns, so a new compositor can be built
+    /// afterwards in the same process.
+    ///
+    /// Returns an error if the backend could not be started, or if
+    /// dispatching events failed.
+    pub fn run(self) -> Result<(), CompositorError> {
+        let mut compositor = self;
+        compositor.start()?;
+        compositor.running = true;
+        while compositor.running {
+            compositor.flush_clients();
+            compositor.dispatch(None)?;
+        }
+        wlr_log!(L_INFO, "Shutting down compositor");
+        Ok(())
+    }
+
+    /// Starts the backend, which announces the outputs and inputs it has
+    /// to the managers.
+    ///
+    /// This only has to be called when driving the compositor with
+    /// `dispatch`, and only does something the first time it's called.
+    pub fn start(&mut self) -> Result<(), CompositorError> {
+        if self.started {
+            return Ok(())
+        }
+        wlr_log!(L_INFO, "Starting compositor");
+        let started = self.with_handle(|compositor| unsafe {
+                                           wlr_backend_start(compositor.backend)
+                                       });
+        if !started {
+            wlr_log!(L_ERROR, "Failed to start backend");
+            return Err(CompositorError::BackendStart)
+        }
+        self.started = true;
+        self.check_panic()
+    }
+
+    /// Runs one iteration of the event loop, which dispatches every event
+    /// that is ready to the handlers.
+    ///
+    /// Waits at most `timeout` for events to arrive, or forever if it's
+    /// `None`. A timeout of zero never blocks.
+    ///
+    /// Use this instead of `run` to drive the compositor from another event
+    /// loop (polling `EventLoop::fd`) or to step it from a test. Remember to
+    /// call `flush_clients` before waiting for events again.
+    ///
+    /// The backend is started first if it wasn't already.
+    pub fn dispatch(&mut self, timeout: Option<Duration>) -> Result<(), CompositorError> {
+        self.start()?;
+        let timeout = timeout.map(duration_to_ms).unwrap_or(-1);
+        let result = self.with_handle(|compositor| unsafe {
+                                          ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                                        wl_event_loop_dispatch,
+                                                        compositor.event_loop.as_ptr(),
+                                                        timeout)
+                                      });
+        // NOTE Read before anything else can overwrite errno.
+        let err = io::Error::last_os_error();
+        self.check_panic()?;
+        if result < 0 {
+            // A signal (e.g SIGCHLD from a spawned client) interrupted the
+            // wait, which is not a failure.
+            if err.kind() == io::ErrorKind::Interrupted {
+                return Ok(())
+            }
+            wlr_log!(L_ERROR, "Failed to dispatch events: {}", err);
+            return Err(CompositorError::Dispatch)
+        }
+        Ok(())
+    }
+
+    /// Returns the panic of a handler that was caught while C code was
+    /// calling into Rust, if there was one.
+    ///
+    /// The compositor is terminated when that happens.
+    fn check_panic(&mut self) -> Result<(), CompositorError> {
+        match take_panic() {
+            Some(payload) => {
+                self.terminate();
+                Err(CompositorError::HandlerPanicked(payload))
+            }
+            None => Ok(())
+        }
+    }
+
+    /// Sends the events that are queued for the clients.
+    pub fn flush_clients(&mut self) {
+        unsafe {
+            ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_flush_clients, self.display);
+        }
+    }
+
+    /// Lets the listeners get back to the compositor while `func` runs.
+    ///
+    /// NOTE The compositor is mutably borrowed for the whole call, so the
+    /// pointer the listeners use is guaranteed not to move.
+    ///
+    /// This can be nested (e.g `spawn_connected` from a key binding), the
+    /// pointer that was set before is restored afterwards.
+    fn with_handle<F, R>(&mut self, func: F) -> R
+        where F: FnOnce(&mut Self) -> R
+    {
+        let handle = self.handle.clone();
+        let previous = handle.replace(self);
+        let result = func(self);
+        handle.set(previous);
+        result
+    }
+
+    /// Gets the name of the socket clients connect to (e.g `wayland-0`).
+    ///
+    /// Returns `None` if the socket was given as a file descriptor.
+    pub fn socket_name(&self) -> Option<&str> {
+        self.socket_name.as_ref().map(String::as_str)
+    }
+
+    /// Starts a program that connects to this compositor.
+    ///
+    /// `WAYLAND_DISPLAY` is set to the socket of the compositor for the
+    /// program, if the socket has a name.
+    ///
+    /// Use `Child::id` to get the pid of the program.
+    pub fn spawn(&mut self, mut command: Command) -> io::Result<Child> {
+        if let Some(ref socket_name) = self.socket_name {
+            command.env("WAYLAND_DISPLAY", socket_name);
+        }
+        command.spawn()
+    }
+
+    /// Starts a program that is already connected to the compositor when
+    /// it starts, by handing it one end of a socket pair in
+    /// `WAYLAND_SOCKET`.
+    ///
+    /// Unlike `spawn` this also works when the socket has no name, and the
+    /// returned `Client` is known to be the program.
+    ///
+    /// Fails with `ConnectionAborted` if the client was disconnected while
+    /// it was created (e.g by `ClientManagerHandler::client_created`). The
+    /// program is killed and reaped whenever it can't be connected.
+    pub fn spawn_connected(&mut self, mut command: Command) -> io::Result<(Client, Child)> {
+        if let Some(ref socket_name) = self.socket_name {
+            command.env("WAYLAND_DISPLAY", socket_name);
+        }
         unsafe {
-            let compositor = UnsafeCell::new(self);
-            if COMPOSITOR_PTR != 0 as _ {
-                // NOTE Rationale for panicking:
-                // * Nicer than an abort
-                // * Not yet in C land
-                panic!("A compositor is already running!")
+            let mut fds = [0; 2];
+            if libc::socketpair(libc::AF_UNIX,
+                                libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+                                0,
+                                fds.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error())
             }
-            COMPOSITOR_PTR = compositor.get();
-            wlr_log!(L_INFO, "Starting compositor");
-            if !wlr_backend_start((*compositor.get()).backend) {
-                wlr_backend_destroy((*compositor.get()).backend);
-                // NOTE Rationale for panicking:
-                // * Won't be in C land just yet, so it's safe to panic
-                // * Can always be returned in a Result instead, but for now
-                //   if you auto create it's assumed you can't recover.
-                panic!("Failed to start backend");
+            let (server_fd, client_fd) = (fds[0], fds[1]);
+            command.env("WAYLAND_SOCKET", client_fd.to_string());
+            // NOTE Only the child's copy of its end may survive the exec.
+            command.pre_exec(move || {
+                                 let flags = libc::fcntl(client_fd, libc::F_GETFD);
+                                 if flags < 0 ||
+                                    libc::fcntl(client_fd,
+                                                libc::F_SETFD,
+                                                flags & !libc::FD_CLOEXEC) < 0 {
+                                     return Err(io::Error::last_os_error())
+                                 }
+                                 Ok(())
+                             });
+            let child = command.spawn();
+            libc::close(client_fd);
+            let child = match child {
+                Ok(child) => child,
+                Err(err) => {
+                    libc::close(server_fd);
+                    return Err(err)
+                }
+            };
+            // The client created callback runs while the client is created.
+            let client = self.with_handle(|compositor| {
+                                              ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                                            wl_client_create,
+                                                            compositor.display,
+                                                            server_fd)
+                                          });
+            if client.is_null() {
+                wlr_log!(L_ERROR, "Could not create client for spawned program");
+                libc::close(server_fd);
+                kill_child(child);
+                return Err(io::Error::new(io::ErrorKind::Other, "Could not create client"))
             }
-            ffi_dispatch!(WAYLAND_SERVER_HANDLE,
-                          wl_display_run,
-                          (*compositor.get()).display);
+            // NOTE The client created handler may have disconnected the client
+            // already, so the pointer can only be trusted once it's found.
+            match self.client_manager.find(client) {
+                Some(client) => Ok((client, child)),
+                None => {
+                    kill_child(child);
+                    Err(io::Error::new(io::ErrorKind::ConnectionAborted,
+                                       "Client was disconnected while it was created"))
+                }
+            }
+        }
+    }
+
+    /// Stops the key repeat of every keyboard, e.g when the keyboard focus
+    /// changes.
+    ///
+    /// See `KeyboardHandle::cancel_repeat`.
+    pub fn cancel_key_repeat(&mut self) {
+        // Forget the keyboards that were removed.
+        self.key_repeats.retain(|repeat| repeat.upgrade().is_some());
+        for repeat in self.key_repeats.iter().filter_map(Weak::upgrade) {
+            repeat.cancel()
+        }
+    }
+
+    /// Tracks the key repeat of a keyboard, so that `cancel_key_repeat`
+    /// reaches it.
+    pub(crate) fn add_key_repeat(&mut self, repeat: &Rc<KeyRepeat>) {
+        self.key_repeats.push(Rc::downgrade(repeat))
+    }
+
+    /// Gets the modifiers held on the keyboard that was used last, or none
+    /// if no keyboard with a handler has been used yet.
+    ///
+    /// Use this to match pointer events against the keyboard (e.g with
+    /// `PointerBindings`).
+    pub fn keyboard_modifiers(&self) -> KeyboardModifier {
+        if self.active_keyboard.is_null() {
+            return KeyboardModifier::empty()
         }
-        // TODO Clean up
+        unsafe {
+            KeyboardModifier::from_bits_truncate(wlr_keyboard_get_modifiers(self.active_keyboard))
+        }
+    }
+
+    /// Makes the keyboard the one that was used last.
+    pub(crate) fn set_active_keyboard(&mut self, keyboard: *mut wlr_keyboard) {
+        self.active_keyboard = keyboard
     }
 
+    /// Forgets the keyboard if it was the one used last, e.g because it was
+    /// removed.
+    pub(crate) fn forget_keyboard(&mut self, keyboard: *mut wlr_keyboard) {
+        if self.active_keyboard == keyboard {
+            self.active_keyboard = ptr::null_mut()
+        }
+    }
+
+    /// Gets the keymap every keyboard gets when it is added.
+    ///
+    /// The default keymap is compiled the first time this is called, which
+    /// fails if the XKB data is missing.
+    pub fn keymap(&mut self) -> Result<&Keymap, KeymapError> {
+        let keymap = match self.keymap.take() {
+            Some(keymap) => keymap,
+            None => Keymap::from_names(&KeymapNames::default())?
+        };
+        Ok(self.keymap.get_or_insert(keymap))
+    }
+
+    /// Gets the clients that are currently connected to the compositor.
+    pub fn clients(&self) -> Vec<Client> {
+        self.client_manager.clients()
+    }
+
+    /// Sets the handler that is called when clients connect and disconnect.
+    ///
+    /// Replaces the previous handler, if there was one. When this is called
+    /// from the handler itself, the old handler is dropped once it returns.
+    pub fn set_client_handler(&mut self, handler: Box<ClientManagerHandler<T>>) {
+        self.client_manager.set_handler(handler)
+    }
+
+    /// Gets the global state of the compositor.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Gets the global state of the compositor mutably.
+    pub fn data_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    /// Gets a handle to the event loop of the compositor, which can be used
+    /// to add timers, file descriptors, signals and idle callbacks to it.
+    pub fn event_loop(&self) -> EventLoop<T> {
+        self.event_loop.clone()
+    }
+
+    /// Stops `run` once the current iteration of the event loop is done.
     pub fn terminate(&mut self) {
+        self.running = false;
         unsafe {
             ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_terminate, self.display);
         }
     }
 }
 
-/// Terminates the compositor.
-/// If one is not running, does nothing
-pub fn terminate() {
-    unsafe {
-        if COMPOSITOR_PTR != 0 as _ {
-            (*COMPOSITOR_PTR).terminate();
-            COMPOSITOR_PTR = 0 as _
+impl<T> Drop for Compositor<T> {
+    /// Destroys everything in the order wlroots expects.
+    ///
+    /// The backend emits the removal of its outputs and inputs while it is
+    /// being destroyed, so the managers are given the compositor one last
+    /// time in order to clean up their listeners and notify the handlers.
+    fn drop(&mut self) {
+        let handle = self.handle.clone();
+        handle.set(self);
+        unsafe {
+            // Clients have to go first, they can still hold on to
+            // resources from the globals below.
+            wl_display_destroy_clients(self.display);
+            self.server_decoration_manager = None;
+            // The renderer uses the backend's EGL context.
+            self.gles2 = None;
+            // NOTE The signals the managers listen to are freed with the
+            // backend, so their listeners must not be removed afterwards.
+            wlr_backend_destroy(self.backend);
+            // Also removes the socket.
+            ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_display_destroy, self.display);
+            self.event_loop.destroyed();
+        }
+        handle.set(ptr::null_mut());
+        // The socket is gone, don't leave its name behind.
+        if let Some((var, previous)) = self.exported_var.take() {
+            match previous {
+                Some(previous) => env::set_var(var, previous),
+                None => env::remove_var(var)
+            }
+        }
+        // A handler panicked during the teardown, continue the panic now
+        // that nothing is left to clean up.
+        if let Some(payload) = take_panic() {
+            if !thread::panicking() {
+                panic::resume_unwind(payload)
+            }
         }
     }
 }
@@ -0,0 +1,440 @@
+//! Safe wrapper around the Wayland event loop of the compositor.
+//!
+//! Use `Compositor::event_loop` to get a handle to it. Every source that is
+//! added runs its callback with the compositor that owns the loop.
+//!
+//! Sources are removed from the event loop when they are dropped, so keep
+//! them around (e.g in the compositor data) for as long as they should fire.
+
+use libc::{c_int, c_void};
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::os::unix::io::RawFd;
+use std::rc::Rc;
+use std::time::Duration;
+
+use compositor::{Compositor, CompositorHandle};
+use ffi::wl_event_loop_add_timer;
+use utils::handle_unwind;
+
+use wayland_sys::server::{WAYLAND_SERVER_HANDLE, wl_event_loop, wl_event_source};
+
+bitflags! {
+    /// The readiness of a file descriptor.
+    pub struct FdEvents: u32 {
+        const WL_EVENT_READABLE = 0x01;
+        const WL_EVENT_WRITABLE = 0x02;
+        const WL_EVENT_HANGUP = 0x04;
+        const WL_EVENT_ERROR = 0x08;
+    }
+}
+
+type TimerCallback<T> = FnMut(&mut Compositor<T>);
+type FdCallback<T> = FnMut(&mut Compositor<T>, RawFd, FdEvents);
+type SignalCallback<T> = FnMut(&mut Compositor<T>, c_int);
+
+/// Handle to the event loop of a compositor.
+///
+/// The handle can outlive the compositor, but once the compositor is dropped
+/// nothing can be added to the loop anymore.
+pub struct EventLoop<T> {
+    event_loop: *mut wl_event_loop,
+    handle: CompositorHandle<T>,
+    /// Set to false once the event loop has been destroyed.
+    alive: Rc<Cell<bool>>,
+    /// The idle callbacks that haven't run yet, which are freed when the
+    /// event loop is destroyed before they do.
+    idles: Rc<RefCell<Vec<PendingIdle>>>
+}
+
+/// The data that is passed to the C callback of a source.
+///
+/// NOTE The callback is reference counted so that the source can be dropped
+/// from within its own callback.
+struct SourceData<T, C: ?Sized> {
+    handle: CompositorHandle<T>,
+    callback: Rc<RefCell<Box<C>>>
+}
+
+/// A source that is removed from the event loop when dropped.
+struct EventSource<T, C: ?Sized> {
+    source: *mut wl_event_source,
+    data: *mut SourceData<T, C>,
+    alive: Rc<Cell<bool>>
+}
+
+/// A timer that runs its callback once it expires.
+///
+/// The timer starts disarmed, use `update` to arm it.
+pub struct TimerSource<T> {
+    source: EventSource<T, TimerCallback<T>>,
+    /// Set to false once the timer is removed.
+    alive: Rc<Cell<bool>>
+}
+
+/// Rearms or disarms a timer without borrowing its `TimerSource`.
+///
+/// It does nothing once the timer or the event loop is gone.
+#[derive(Clone)]
+pub(crate) struct TimerHandle {
+    source: *mut wl_event_source,
+    timer_alive: Rc<Cell<bool>>,
+    loop_alive: Rc<Cell<bool>>
+}
+
+/// A file descriptor that runs its callback when it's ready.
+pub struct FdSource<T>(EventSource<T, FdCallback<T>>);
+
+/// A POSIX signal that runs its callback when it is delivered.
+pub struct SignalSource<T>(EventSource<T, SignalCallback<T>>);
+
+struct IdleData<T, F> {
+    handle: CompositorHandle<T>,
+    callback: F,
+    idles: Rc<RefCell<Vec<PendingIdle>>>
+}
+
+/// The type erased data of an idle callback that hasn't run yet.
+struct PendingIdle {
+    data: *mut c_void,
+    free: unsafe fn(*mut c_void)
+}
+
+impl<T> Clone for EventLoop<T> {
+    fn clone(&self) -> Self {
+        EventLoop {
+            event_loop: self.event_loop,
+            handle: self.handle.clone(),
+            alive: self.alive.clone(),
+            idles: self.idles.clone()
+        }
+    }
+}
+
+impl<T> EventLoop<T> {
+    pub(crate) unsafe fn new(event_loop: *mut wl_event_loop, handle: CompositorHandle<T>) -> Self {
+        EventLoop {
+            event_loop,
+            handle,
+            alive: Rc::new(Cell::new(true)),
+            idles: Rc::new(RefCell::new(Vec::new()))
+        }
+    }
+
+    pub(crate) unsafe fn as_ptr(&self) -> *mut wl_event_loop {
+        self.event_loop
+    }
+
+    /// Marks the event loop as destroyed, so that the sources don't try to
+    /// remove themselves from it when they are dropped.
+    ///
+    /// The idle callbacks that never ran are freed.
+    pub(crate) fn destroyed(&self) {
+        self.alive.set(false);
+        // NOTE Taken out first, dropping a callback could use the list.
+        let idles = mem::replace(&mut *self.idles.borrow_mut(), Vec::new());
+        for idle in idles {
+            unsafe { (idle.free)(idle.data) }
+        }
+    }
+
+    /// Gets the file descriptor of the event loop.
+    ///
+    /// It becomes readable whenever there are events to dispatch, so it can
+    /// be polled by another event loop which then calls
+    /// `Compositor::dispatch`.
+    ///
+    /// Returns `None` if the compositor has been dropped.
+    pub fn fd(&self) -> Option<RawFd> {
+        if !self.alive.get() {
+            return None
+        }
+        unsafe {
+            Some(ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_event_loop_get_fd, self.event_loop))
+        }
+    }
+
+    /// Adds a timer to the event loop.
+    ///
+    /// Returns `None` if the timer could not be created, or if the
+    /// compositor has been dropped.
+    pub fn add_timer<F>(&self, callback: F) -> Option<TimerSource<T>>
+        where F: FnMut(&mut Compositor<T>) + 'static
+    {
+        if !self.alive.get() {
+            return None
+        }
+        unsafe {
+            let data = self.source_data(Box::new(callback) as Box<TimerCallback<T>>);
+            let source =
+                wl_event_loop_add_timer(self.event_loop, timer_notify::<T>, data as *mut _);
+            let alive = Rc::new(Cell::new(true));
+            self.event_source(source, data).map(|source| TimerSource { source, alive })
+        }
+    }
+
+    /// Adds a file descriptor to the event loop. The callback is run when the
+    /// file descriptor is ready for any of the events in `mask`.
+    ///
+    /// The file descriptor is not closed when the source is dropped.
+    ///
+    /// Returns `None` if the file descriptor could not be added, or if the
+    /// compositor has been dropped.
+    pub fn add_fd<F>(&self, fd: RawFd, mask: FdEvents, callback: F) -> Option<FdSource<T>>
+        where F: FnMut(&mut Compositor<T>, RawFd, FdEvents) + 'static
+    {
+        if !self.alive.get() {
+            return None
+        }
+        unsafe {
+            let data = self.source_data(Box::new(callback) as Box<FdCallback<T>>);
+            let source = ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                       wl_event_loop_add_fd,
+                                       self.event_loop,
+                                       fd,
+                                       mask.bits(),
+                                       fd_notify::<T>,
+                                       data as *mut _);
+            self.event_source(source, data).map(FdSource)
+        }
+    }
+
+    /// Adds a POSIX signal (e.g `libc::SIGTERM`) to the event loop.
+    ///
+    /// The signal is blocked for the current thread, so it's only delivered
+    /// through the event loop.
+    ///
+    /// Returns `None` if the signal could not be added, or if the compositor
+    /// has been dropped.
+    pub fn add_signal<F>(&self, signal: c_int, callback: F) -> Option<SignalSource<T>>
+        where F: FnMut(&mut Compositor<T>, c_int) + 'static
+    {
+        if !self.alive.get() {
+            return None
+        }
+        unsafe {
+            let data = self.source_data(Box::new(callback) as Box<SignalCallback<T>>);
+            let source = ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                       wl_event_loop_add_signal,
+                                       self.event_loop,
+                                       signal,
+                                       signal_notify::<T>,
+                                       data as *mut _);
+            self.event_source(source, data).map(SignalSource)
+        }
+    }
+
+    /// Runs the callback once the event loop has nothing else to do.
+    ///
+    /// Returns false if the callback could not be added, or if the
+    /// compositor has been dropped.
+    pub fn add_idle<F>(&self, callback: F) -> bool
+        where F: FnOnce(&mut Compositor<T>) + 'static
+    {
+        if !self.alive.get() {
+            return false
+        }
+        unsafe {
+            let data = Box::into_raw(Box::new(IdleData {
+                                                  handle: self.handle.clone(),
+                                                  callback,
+                                                  idles: self.idles.clone()
+                                              }));
+            let source = ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                       wl_event_loop_add_idle,
+                                       self.event_loop,
+                                       idle_notify::<T, F>,
+                                       data as *mut _);
+            if source.is_null() {
+                wlr_log!(L_ERROR, "Could not add idle callback to the event loop");
+                drop(Box::from_raw(data));
+                false
+            } else {
+                self.idles.borrow_mut().push(PendingIdle {
+                                                 data: data as *mut c_void,
+                                                 free: free_idle::<T, F>
+                                             });
+                true
+            }
+        }
+    }
+
+    fn source_data<C: ?Sized>(&self, callback: Box<C>) -> *mut SourceData<T, C> {
+        Box::into_raw(Box::new(SourceData {
+                                   handle: self.handle.clone(),
+                                   callback: Rc::new(RefCell::new(callback))
+                               }))
+    }
+
+    unsafe fn event_source<C: ?Sized>(&self,
+                                      source: *mut wl_event_source,
+                                      data: *mut SourceData<T, C>)
+                                      -> Option<EventSource<T, C>> {
+        if source.is_null() {
+            wlr_log!(L_ERROR, "Could not add source to the event loop");
+            drop(Box::from_raw(data));
+            None
+        } else {
+            Some(EventSource {
+                     source,
+                     data,
+                     alive: self.alive.clone()
+                 })
+        }
+    }
+}
+
+impl<T> TimerSource<T> {
+    /// Arms the timer to fire once after `delay`.
+    /// If it was already armed, it's rearmed with the new delay.
+    ///
+    /// The delay has millisecond precision, a delay of zero disarms the timer.
+    pub fn update(&mut self, delay: Duration) {
+        self.source.timer_update(duration_to_ms(delay))
+    }
+
+    /// Disarms the timer without removing it from the event loop.
+    pub fn cancel(&mut self) {
+        self.source.timer_update(0)
+    }
+
+    pub(crate) fn handle(&self) -> TimerHandle {
+        TimerHandle {
+            source: self.source.source,
+            timer_alive: self.alive.clone(),
+            loop_alive: self.source.alive.clone()
+        }
+    }
+}
+
+impl<T> Drop for TimerSource<T> {
+    fn drop(&mut self) {
+        self.alive.set(false)
+    }
+}
+
+impl TimerHandle {
+    /// Disarms the timer without removing it from the event loop.
+    pub(crate) fn cancel(&self) {
+        if self.timer_alive.get() && self.loop_alive.get() {
+            unsafe {
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_event_source_timer_update, self.source, 0);
+            }
+        }
+    }
+}
+
+impl<T> FdSource<T> {
+    /// Changes the events the file descriptor is watched for.
+    pub fn update(&mut self, mask: FdEvents) {
+        if self.0.alive.get() {
+            unsafe {
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                              wl_event_source_fd_update,
+                              self.0.source,
+                              mask.bits());
+            }
+        }
+    }
+}
+
+impl<T, C: ?Sized> EventSource<T, C> {
+    fn timer_update(&mut self, ms: c_int) {
+        if self.alive.get() {
+            unsafe {
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_event_source_timer_update, self.source, ms);
+            }
+        }
+    }
+}
+
+impl<T, C: ?Sized> Drop for EventSource<T, C> {
+    fn drop(&mut self) {
+        unsafe {
+            if self.alive.get() {
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_event_source_remove, self.source);
+            }
+            drop(Box::from_raw(self.data));
+        }
+    }
+}
+
+/// Converts a duration to milliseconds, saturating at the largest timeout
+/// Wayland accepts.
+///
+/// Sub-millisecond parts are rounded up, so only a zero duration becomes 0
+/// (which disarms a timer).
+pub(crate) fn duration_to_ms(duration: Duration) -> c_int {
+    let ms = duration.as_secs()
+        .saturating_mul(1000)
+        .saturating_add(((duration.subsec_nanos() + 999_999) / 1_000_000) as u64);
+    if ms > c_int::max_value() as u64 {
+        c_int::max_value()
+    } else {
+        ms as c_int
+    }
+}
+
+/// Gets the compositor and a strong reference to the callback out of the
+/// source data.
+unsafe fn source_callback<'compositor, T, C: ?Sized>
+    (data: *mut c_void)
+     -> Option<(&'compositor mut Compositor<T>, Rc<RefCell<Box<C>>>)> {
+    let data = &*(data as *mut SourceData<T, C>);
+    let callback = data.callback.clone();
+    data.handle.get().map(|compositor| (compositor, callback))
+}
+
+unsafe extern "C" fn timer_notify<T>(data: *mut c_void) -> c_int {
+    if let Some((compositor, callback)) = source_callback::<T, TimerCallback<T>>(data) {
+        handle_unwind(|| (&mut **callback.borrow_mut())(compositor));
+    }
+    0
+}
+
+unsafe extern "C" fn fd_notify<T>(fd: c_int, mask: u32, data: *mut c_void) -> c_int {
+    if let Some((compositor, callback)) = source_callback::<T, FdCallback<T>>(data) {
+        let mask = FdEvents::from_bits_truncate(mask);
+        handle_unwind(|| (&mut **callback.borrow_mut())(compositor, fd, mask));
+    }
+    0
+}
+
+unsafe extern "C" fn signal_notify<T>(signal: c_int, data: *mut c_void) -> c_int {
+    if let Some((compositor, callback)) = source_callback::<T, SignalCallback<T>>(data) {
+        handle_unwind(|| (&mut **callback.borrow_mut())(compositor, signal));
+    }
+    0
+}
+
+unsafe extern "C" fn idle_notify<T, F>(data: *mut c_void)
+    where F: FnOnce(&mut Compositor<T>)
+{
+    // NOTE Idle sources are freed by Wayland after they fire.
+    let idle_data = Box::from_raw(data as *mut IdleData<T, F>);
+    let IdleData { handle, callback, idles } = *idle_data;
+    idles.borrow_mut().retain(|idle| idle.data != data);
+    if let Some(compositor) = handle.get() {
+        handle_unwind(|| callback(compositor));
+    }
+}
+
+/// Frees the data of an idle callback that never ran.
+unsafe fn free_idle<T, F>(data: *mut c_void) {
+    drop(Box::from_raw(data as *mut IdleData<T, F>));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_to_ms_rounds_up() {
+        assert_eq!(duration_to_ms(Duration::from_secs(0)), 0);
+        assert_eq!(duration_to_ms(Duration::new(0, 1)), 1);
+        assert_eq!(duration_to_ms(Duration::from_millis(500) / 1000), 1);
+        assert_eq!(duration_to_ms(Duration::from_millis(1)), 1);
+        assert_eq!(duration_to_ms(Duration::new(1, 1_000_001)), 1002);
+        assert_eq!(duration_to_ms(Duration::from_secs(u64::max_value())), c_int::max_value());
+    }
+}
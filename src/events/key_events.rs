@@ -1,29 +1,112 @@
-use wlroots_sys::{wlr_event_keyboard_key, xkb_keysym_t, xkb_state, xkb_state_key_get_syms};
+use libc::c_char;
+use std::ptr;
+
+use wlroots_sys::{wlr_event_keyboard_key, wlr_key_state, wlr_keyboard, xkb_keymap,
+                  xkb_keymap_key_get_syms_by_level, xkb_keysym_t, xkb_state,
+                  xkb_state_key_get_layout, xkb_state_key_get_syms, xkb_state_key_get_utf8};
 
 pub type Key = xkb_keysym_t;
 
 #[derive(Debug)]
 pub struct KeyEvent {
     key: *mut wlr_event_keyboard_key,
-    xkb_state: *mut xkb_state
+    xkb_state: *mut xkb_state,
+    keymap: *mut xkb_keymap,
+    repeated: bool
 }
 
 impl KeyEvent {
-    pub(crate) unsafe fn new(key: *mut wlr_event_keyboard_key, xkb_state: *mut xkb_state) -> Self {
-        KeyEvent { key, xkb_state }
+    pub(crate) unsafe fn new(key: *mut wlr_event_keyboard_key,
+                             keyboard: *mut wlr_keyboard,
+                             repeated: bool)
+                             -> Self {
+        KeyEvent {
+            key,
+            xkb_state: (*keyboard).xkb_state,
+            keymap: (*keyboard).keymap,
+            repeated
+        }
     }
 
     pub fn keycode(&self) -> u32 {
         unsafe { (*self.key).keycode + 8 }
     }
 
-    pub fn input_keys(&self) -> Vec<Key> {
+    /// Determines if the key was pressed or released.
+    pub fn state(&self) -> wlr_key_state {
+        unsafe { (*self.key).state }
+    }
+
+    /// Determines if the event was made by the key repeat of the compositor,
+    /// rather than by the key actually being pressed.
+    ///
+    /// See `KeyboardHandle::set_repeat_enabled`.
+    pub fn repeated(&self) -> bool {
+        self.repeated
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.key).time_msec }
+    }
+
+    /// Determines if the key updates the XKB state of the keyboard
+    /// (e.g the modifiers).
+    pub fn update_state(&self) -> bool {
+        unsafe { (*self.key).update_state }
+    }
+
+    /// Gets the text the key produces with the current modifiers, which is
+    /// empty if it doesn't produce any (e.g for Shift or Escape).
+    pub fn utf8(&self) -> String {
         unsafe {
-            let mut syms = 0 as *const xkb_keysym_t;
+            let len = xkb_state_key_get_utf8(self.xkb_state, self.keycode(), ptr::null_mut(), 0);
+            if len <= 0 {
+                return String::new()
+            }
+            // One more for the nul terminator.
+            let mut buffer = vec![0u8; len as usize + 1];
+            xkb_state_key_get_utf8(self.xkb_state,
+                                   self.keycode(),
+                                   buffer.as_mut_ptr() as *mut c_char,
+                                   buffer.len());
+            buffer.truncate(len as usize);
+            String::from_utf8_lossy(&buffer).into_owned()
+        }
+    }
+
+    /// Gets the keysyms of the key without any modifiers applied
+    /// (e.g `a` for Shift+a).
+    ///
+    /// Use these for bindings that include modifiers.
+    pub fn raw_keysyms(&self) -> Vec<Key> {
+        unsafe {
+            let layout = xkb_state_key_get_layout(self.xkb_state, self.keycode());
+            let mut syms = ptr::null();
+            let key_length =
+                xkb_keymap_key_get_syms_by_level(self.keymap, self.keycode(), layout, 0, &mut syms);
+            (0..key_length)
+                .map(|index| *syms.offset(index as isize))
+                .collect()
+        }
+    }
+
+    /// Gets the keysyms of the key with the active modifiers applied
+    /// (e.g `A` for Shift+a).
+    pub fn translated_keysyms(&self) -> Vec<Key> {
+        unsafe {
+            let mut syms = ptr::null();
             let key_length = xkb_state_key_get_syms(self.xkb_state, self.keycode(), &mut syms);
             (0..key_length)
                 .map(|index| *syms.offset(index as isize))
                 .collect()
         }
     }
+
+    /// Gets the keysyms of the key with the active modifiers applied.
+    ///
+    /// Same as `translated_keysyms`.
+    pub fn input_keys(&self) -> Vec<Key> {
+        self.translated_keysyms()
+    }
 }
@@ -1,2 +1,12 @@
 pub mod key_events;
 pub mod pointer_events;
+pub mod tablet_pad_events;
+pub mod tablet_tool_events;
+pub mod touch_events;
+
+/// Converts a position in millimetres to a fraction of the device size.
+fn normalize(x_mm: f64, y_mm: f64, width_mm: f64, height_mm: f64) -> (f64, f64) {
+    let x = if width_mm > 0.0 { x_mm / width_mm } else { 0.0 };
+    let y = if height_mm > 0.0 { y_mm / height_mm } else { 0.0 };
+    (x, y)
+}
@@ -1,22 +1,32 @@
 //! Pointers and their events
 
+use std::ptr;
+
+use super::normalize;
 use types::input_device::InputDevice;
+use types::output::OutputLayout;
 
-use wlroots_sys::{wlr_button_state, wlr_event_pointer_axis, wlr_event_pointer_button,
-                  wlr_event_pointer_motion, wlr_event_pointer_motion_absolute};
+use wlroots_sys::{wlr_axis_orientation, wlr_axis_source, wlr_button_state,
+                  wlr_event_pointer_axis, wlr_event_pointer_button, wlr_event_pointer_motion,
+                  wlr_event_pointer_motion_absolute, wlr_output_layout_get_box};
 
+/// The pointer scrolled (e.g the wheel of a mouse turned).
 pub struct AxisEvent {
     event: *mut wlr_event_pointer_axis
 }
 
+/// A button of the pointer was pressed or released.
 pub struct ButtonEvent {
     event: *mut wlr_event_pointer_button
 }
 
+/// The pointer moved relative to where it was (e.g a mouse).
 pub struct MotionEvent {
     event: *mut wlr_event_pointer_motion
 }
 
+/// The pointer moved to a position on the device (e.g a pen on a tablet that
+/// acts like a pointer).
 pub struct AbsoluteMotionEvent {
     event: *mut wlr_event_pointer_motion_absolute
 }
@@ -26,6 +36,15 @@ impl ButtonEvent {
         ButtonEvent { event }
     }
 
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
     pub fn state(&self) -> wlr_button_state {
         unsafe { (*self.event).state }
     }
@@ -40,9 +59,36 @@ impl AxisEvent {
         AxisEvent { event }
     }
 
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets what made the pointer scroll (e.g a wheel or a finger).
+    pub fn source(&self) -> wlr_axis_source {
+        unsafe { (*self.event).source }
+    }
+
+    /// Determines if the pointer scrolled vertically or horizontally.
+    pub fn orientation(&self) -> wlr_axis_orientation {
+        unsafe { (*self.event).orientation }
+    }
+
+    /// Gets how far the pointer scrolled, positive values are down or to
+    /// the right.
     pub fn delta(&self) -> f64 {
         unsafe { (*self.event).delta }
     }
+
+    /// Gets how many discrete steps the pointer scrolled (e.g clicks of a
+    /// wheel), or 0 if the source is continuous (e.g a finger).
+    pub fn delta_discrete(&self) -> i32 {
+        unsafe { (*self.event).delta_discrete }
+    }
 }
 
 impl MotionEvent {
@@ -54,6 +100,11 @@ impl MotionEvent {
         unsafe { InputDevice::from_ptr((*self.event).device) }
     }
 
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
     pub fn delta(&self) -> (f64, f64) {
         unsafe { ((*self.event).delta_x, (*self.event).delta_y) }
     }
@@ -67,4 +118,53 @@ impl AbsoluteMotionEvent {
     pub fn device(&self) -> InputDevice {
         unsafe { InputDevice::from_ptr((*self.event).device) }
     }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets the position of the pointer on the device, from (0, 0) in the
+    /// top left corner to (1, 1) in the bottom right corner.
+    pub fn position(&self) -> (f64, f64) {
+        normalize(self.x_mm(), self.y_mm(), self.width_mm(), self.height_mm())
+    }
+
+    /// Gets the horizontal position of the pointer on the device in
+    /// millimetres.
+    pub fn x_mm(&self) -> f64 {
+        unsafe { (*self.event).x_mm }
+    }
+
+    /// Gets the vertical position of the pointer on the device in
+    /// millimetres.
+    pub fn y_mm(&self) -> f64 {
+        unsafe { (*self.event).y_mm }
+    }
+
+    /// Gets the width of the device in millimetres.
+    pub fn width_mm(&self) -> f64 {
+        unsafe { (*self.event).width_mm }
+    }
+
+    /// Gets the height of the device in millimetres.
+    pub fn height_mm(&self) -> f64 {
+        unsafe { (*self.event).height_mm }
+    }
+
+    /// Maps the position of the pointer on the device onto the whole output
+    /// layout, and gets it in layout coordinates (e.g to warp a `Cursor`).
+    ///
+    /// Returns `None` if the layout has no box (e.g it has no outputs).
+    pub fn layout_coords(&self, layout: &OutputLayout) -> Option<(f64, f64)> {
+        let (x, y) = self.position();
+        unsafe {
+            let layout_box = wlr_output_layout_get_box(layout.to_ptr(), ptr::null_mut());
+            if layout_box.is_null() {
+                return None
+            }
+            Some(((*layout_box).x as f64 + x * (*layout_box).width as f64,
+                  (*layout_box).y as f64 + y * (*layout_box).height as f64))
+        }
+    }
 }
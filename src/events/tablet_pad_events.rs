@@ -0,0 +1,93 @@
+//! Tablet pads and their events
+
+use wlroots_sys::{wlr_button_state, wlr_event_tablet_pad_button, wlr_event_tablet_pad_ring,
+                  wlr_event_tablet_pad_strip, wlr_tablet_pad_ring_source,
+                  wlr_tablet_pad_strip_source};
+
+/// A button on the pad was pressed or released.
+pub struct TabletPadButtonEvent {
+    event: *mut wlr_event_tablet_pad_button
+}
+
+/// A ring on the pad was turned.
+pub struct TabletPadRingEvent {
+    event: *mut wlr_event_tablet_pad_ring
+}
+
+/// A finger moved along a strip on the pad.
+pub struct TabletPadStripEvent {
+    event: *mut wlr_event_tablet_pad_strip
+}
+
+impl TabletPadButtonEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_tablet_pad_button) -> Self {
+        TabletPadButtonEvent { event }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    pub fn button(&self) -> u32 {
+        unsafe { (*self.event).button }
+    }
+
+    pub fn state(&self) -> wlr_button_state {
+        unsafe { (*self.event).state }
+    }
+}
+
+impl TabletPadRingEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_tablet_pad_ring) -> Self {
+        TabletPadRingEvent { event }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets what is turning the ring (e.g a finger).
+    pub fn source(&self) -> wlr_tablet_pad_ring_source {
+        unsafe { (*self.event).source }
+    }
+
+    /// Gets the index of the ring that was turned.
+    pub fn ring(&self) -> u32 {
+        unsafe { (*self.event).ring }
+    }
+
+    /// Gets the position on the ring in degrees, or -1 when the finger was
+    /// lifted from it.
+    pub fn position(&self) -> f64 {
+        unsafe { (*self.event).position }
+    }
+}
+
+impl TabletPadStripEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_tablet_pad_strip) -> Self {
+        TabletPadStripEvent { event }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets what is moving along the strip (e.g a finger).
+    pub fn source(&self) -> wlr_tablet_pad_strip_source {
+        unsafe { (*self.event).source }
+    }
+
+    /// Gets the index of the strip that was used.
+    pub fn strip(&self) -> u32 {
+        unsafe { (*self.event).strip }
+    }
+
+    /// Gets the position on the strip, from 0 to 1, or -1 when the finger
+    /// was lifted from it.
+    pub fn position(&self) -> f64 {
+        unsafe { (*self.event).position }
+    }
+}
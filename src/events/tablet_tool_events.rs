@@ -0,0 +1,219 @@
+//! Tablet tools (e.g pens) and their events
+
+use super::normalize;
+use types::input_device::InputDevice;
+
+use wlroots_sys::{wlr_button_state, wlr_event_tablet_tool_axis, wlr_event_tablet_tool_button,
+                  wlr_event_tablet_tool_proximity, wlr_event_tablet_tool_tip,
+                  wlr_tablet_tool_axes, wlr_tablet_tool_proximity_state,
+                  wlr_tablet_tool_tip_state};
+
+bitflags! {
+    /// The axes of a tablet tool that changed in an axis event.
+    pub struct TabletToolAxes: u32 {
+        const WLR_TABLET_TOOL_AXIS_X = wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_X as u32;
+        const WLR_TABLET_TOOL_AXIS_Y = wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_Y as u32;
+        const WLR_TABLET_TOOL_AXIS_DISTANCE =
+            wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_DISTANCE as u32;
+        const WLR_TABLET_TOOL_AXIS_PRESSURE =
+            wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_PRESSURE as u32;
+        const WLR_TABLET_TOOL_AXIS_TILT_X =
+            wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_TILT_X as u32;
+        const WLR_TABLET_TOOL_AXIS_TILT_Y =
+            wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_TILT_Y as u32;
+        const WLR_TABLET_TOOL_AXIS_ROTATION =
+            wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_ROTATION as u32;
+        const WLR_TABLET_TOOL_AXIS_SLIDER =
+            wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_SLIDER as u32;
+        const WLR_TABLET_TOOL_AXIS_WHEEL =
+            wlr_tablet_tool_axes::WLR_TABLET_TOOL_AXIS_WHEEL as u32;
+    }
+}
+
+/// One or more axes of the tool changed (e.g it moved, or the pressure on
+/// the tip changed).
+pub struct TabletToolAxisEvent {
+    event: *mut wlr_event_tablet_tool_axis
+}
+
+/// The tool came close enough to the tablet to be detected, or left it.
+pub struct TabletToolProximityEvent {
+    event: *mut wlr_event_tablet_tool_proximity
+}
+
+/// The tip of the tool touched the tablet, or was lifted from it.
+pub struct TabletToolTipEvent {
+    event: *mut wlr_event_tablet_tool_tip
+}
+
+/// A button on the tool was pressed or released.
+pub struct TabletToolButtonEvent {
+    event: *mut wlr_event_tablet_tool_button
+}
+
+impl TabletToolAxisEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_tablet_tool_axis) -> Self {
+        TabletToolAxisEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets the axes that changed, the values of the others are stale.
+    pub fn updated_axes(&self) -> TabletToolAxes {
+        unsafe { TabletToolAxes::from_bits_truncate((*self.event).updated_axes) }
+    }
+
+    /// Gets the position of the tool on the tablet, from (0, 0) in the top
+    /// left corner to (1, 1) in the bottom right corner.
+    pub fn position(&self) -> (f64, f64) {
+        let (x_mm, y_mm) = self.position_mm();
+        let (width_mm, height_mm) = self.size_mm();
+        normalize(x_mm, y_mm, width_mm, height_mm)
+    }
+
+    /// Gets the position of the tool on the tablet in millimetres.
+    pub fn position_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).x_mm, (*self.event).y_mm) }
+    }
+
+    /// Gets the size of the tablet in millimetres.
+    pub fn size_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).width_mm, (*self.event).height_mm) }
+    }
+
+    /// Gets the pressure on the tip, from 0 to 1.
+    pub fn pressure(&self) -> f64 {
+        unsafe { (*self.event).pressure }
+    }
+
+    /// Gets the distance of the tool from the tablet, from 0 to 1.
+    pub fn distance(&self) -> f64 {
+        unsafe { (*self.event).distance }
+    }
+
+    /// Gets the (x, y) tilt of the tool in degrees.
+    pub fn tilt(&self) -> (f64, f64) {
+        unsafe { ((*self.event).tilt_x, (*self.event).tilt_y) }
+    }
+
+    /// Gets the rotation of the tool in degrees.
+    pub fn rotation(&self) -> f64 {
+        unsafe { (*self.event).rotation }
+    }
+
+    /// Gets the position of the slider on the tool, from -1 to 1.
+    pub fn slider(&self) -> f64 {
+        unsafe { (*self.event).slider }
+    }
+
+    /// Gets how far the wheel on the tool turned, in degrees.
+    pub fn wheel_delta(&self) -> f64 {
+        unsafe { (*self.event).wheel_delta }
+    }
+}
+
+impl TabletToolProximityEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_tablet_tool_proximity) -> Self {
+        TabletToolProximityEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Determines if the tool came into proximity or left it.
+    pub fn state(&self) -> wlr_tablet_tool_proximity_state {
+        unsafe { (*self.event).state }
+    }
+
+    /// Gets the position of the tool on the tablet, from (0, 0) in the top
+    /// left corner to (1, 1) in the bottom right corner.
+    pub fn position(&self) -> (f64, f64) {
+        let (x_mm, y_mm) = self.position_mm();
+        let (width_mm, height_mm) = self.size_mm();
+        normalize(x_mm, y_mm, width_mm, height_mm)
+    }
+
+    /// Gets the position of the tool on the tablet in millimetres.
+    pub fn position_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).x_mm, (*self.event).y_mm) }
+    }
+
+    /// Gets the size of the tablet in millimetres.
+    pub fn size_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).width_mm, (*self.event).height_mm) }
+    }
+}
+
+impl TabletToolTipEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_tablet_tool_tip) -> Self {
+        TabletToolTipEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Determines if the tip touched the tablet or was lifted.
+    pub fn state(&self) -> wlr_tablet_tool_tip_state {
+        unsafe { (*self.event).state }
+    }
+
+    /// Gets the position of the tool on the tablet, from (0, 0) in the top
+    /// left corner to (1, 1) in the bottom right corner.
+    pub fn position(&self) -> (f64, f64) {
+        let (x_mm, y_mm) = self.position_mm();
+        let (width_mm, height_mm) = self.size_mm();
+        normalize(x_mm, y_mm, width_mm, height_mm)
+    }
+
+    /// Gets the position of the tool on the tablet in millimetres.
+    pub fn position_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).x_mm, (*self.event).y_mm) }
+    }
+
+    /// Gets the size of the tablet in millimetres.
+    pub fn size_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).width_mm, (*self.event).height_mm) }
+    }
+}
+
+impl TabletToolButtonEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_tablet_tool_button) -> Self {
+        TabletToolButtonEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    pub fn button(&self) -> u32 {
+        unsafe { (*self.event).button }
+    }
+
+    pub fn state(&self) -> wlr_button_state {
+        unsafe { (*self.event).state }
+    }
+}
@@ -0,0 +1,145 @@
+//! Touch screens and their events
+
+use super::normalize;
+use types::input_device::InputDevice;
+
+use wlroots_sys::{wlr_event_touch_cancel, wlr_event_touch_down, wlr_event_touch_motion,
+                  wlr_event_touch_up};
+
+/// A finger touched the screen.
+pub struct TouchDownEvent {
+    event: *mut wlr_event_touch_down
+}
+
+/// A finger was lifted from the screen.
+pub struct TouchUpEvent {
+    event: *mut wlr_event_touch_up
+}
+
+/// A finger that is touching the screen moved.
+pub struct TouchMotionEvent {
+    event: *mut wlr_event_touch_motion
+}
+
+/// The touch point is no longer valid (e.g the touch became a gesture of the
+/// device itself). Treat it like it was lifted without acting on it.
+pub struct TouchCancelEvent {
+    event: *mut wlr_event_touch_cancel
+}
+
+impl TouchDownEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_touch_down) -> Self {
+        TouchDownEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets the id of the touch point, which stays the same until the
+    /// finger is lifted.
+    pub fn slot(&self) -> i32 {
+        unsafe { (*self.event).slot }
+    }
+
+    /// Gets the position of the touch point on the device, from (0, 0) in
+    /// the top left corner to (1, 1) in the bottom right corner.
+    pub fn position(&self) -> (f64, f64) {
+        let (x_mm, y_mm) = self.position_mm();
+        let (width_mm, height_mm) = self.size_mm();
+        normalize(x_mm, y_mm, width_mm, height_mm)
+    }
+
+    /// Gets the position of the touch point on the device in millimetres.
+    pub fn position_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).x_mm, (*self.event).y_mm) }
+    }
+
+    /// Gets the size of the device in millimetres.
+    pub fn size_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).width_mm, (*self.event).height_mm) }
+    }
+}
+
+impl TouchUpEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_touch_up) -> Self {
+        TouchUpEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets the id of the touch point that was lifted.
+    pub fn slot(&self) -> i32 {
+        unsafe { (*self.event).slot }
+    }
+}
+
+impl TouchMotionEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_touch_motion) -> Self {
+        TouchMotionEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets the id of the touch point that moved.
+    pub fn slot(&self) -> i32 {
+        unsafe { (*self.event).slot }
+    }
+
+    /// Gets the new position of the touch point on the device, from (0, 0)
+    /// in the top left corner to (1, 1) in the bottom right corner.
+    pub fn position(&self) -> (f64, f64) {
+        let (x_mm, y_mm) = self.position_mm();
+        let (width_mm, height_mm) = self.size_mm();
+        normalize(x_mm, y_mm, width_mm, height_mm)
+    }
+
+    /// Gets the new position of the touch point on the device in millimetres.
+    pub fn position_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).x_mm, (*self.event).y_mm) }
+    }
+
+    /// Gets the size of the device in millimetres.
+    pub fn size_mm(&self) -> (f64, f64) {
+        unsafe { ((*self.event).width_mm, (*self.event).height_mm) }
+    }
+}
+
+impl TouchCancelEvent {
+    pub unsafe fn from_ptr(event: *mut wlr_event_touch_cancel) -> Self {
+        TouchCancelEvent { event }
+    }
+
+    pub fn device(&self) -> InputDevice {
+        unsafe { InputDevice::from_ptr((*self.event).device) }
+    }
+
+    /// Gets the time of the event in milliseconds.
+    pub fn time_msec(&self) -> u32 {
+        unsafe { (*self.event).time_msec }
+    }
+
+    /// Gets the id of the touch point that was cancelled.
+    pub fn slot(&self) -> i32 {
+        unsafe { (*self.event).slot }
+    }
+}
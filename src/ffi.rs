@@ -0,0 +1,101 @@
+//! Bindings to libwayland-server functions that aren't exposed by
+//! wayland-sys yet, and to the libinput configuration functions.
+//!
+//! libwayland-server and libinput are always linked in by wlroots-sys, so
+//! these can be called directly.
+
+use libc::{c_char, c_double, c_int, c_void, size_t};
+
+use wayland_sys::common::wl_interface;
+use wayland_sys::server::{wl_client, wl_display, wl_event_loop, wl_event_source, wl_global};
+use wlroots_sys::{libinput_device, log_callback_t, log_importance_t};
+
+/// The `va_list` of the wlroots log callback, as bindgen generated it.
+///
+/// NOTE How `va_list` is passed differs between platforms (e.g it's
+/// `*mut __va_list_tag` on x86_64 but a struct on aarch64), so it's taken
+/// from the signature of `log_callback_t` instead of being spelled out.
+pub type VaList = <log_callback_t as VaListArg>::Arg;
+
+/// Gets the type of the `va_list` argument of a log callback.
+pub trait VaListArg {
+    type Arg;
+}
+
+impl<A> VaListArg for Option<unsafe extern "C" fn(log_importance_t, *const c_char, A)> {
+    type Arg = A;
+}
+
+extern "C" {
+    /// Disconnects every client connected to the display.
+    pub fn wl_display_destroy_clients(display: *mut wl_display);
+
+    /// Only the globals `filter` returns true for are advertised to, and can
+    /// be bound by, a client.
+    pub fn wl_display_set_global_filter(display: *mut wl_display,
+                                        filter: unsafe extern "C" fn(*const wl_client,
+                                                                     *const wl_global,
+                                                                     *mut c_void)
+                                                                     -> bool,
+                                        data: *mut c_void);
+
+    pub fn wl_global_get_interface(global: *const wl_global) -> *const wl_interface;
+
+    /// NOTE This is misspelled as `wl_event_lopp_add_timer` in wayland-sys.
+    pub fn wl_event_loop_add_timer(event_loop: *mut wl_event_loop,
+                                   func: unsafe extern "C" fn(*mut c_void) -> c_int,
+                                   data: *mut c_void)
+                                   -> *mut wl_event_source;
+
+    /// NOTE The libc crate can't bind this, since it has no `va_list` type.
+    pub fn vsnprintf(buffer: *mut c_char,
+                     size: size_t,
+                     format: *const c_char,
+                     args: VaList)
+                     -> c_int;
+}
+
+// The results of setting a libinput configuration option,
+// anything else means the value was invalid.
+pub const LIBINPUT_CONFIG_STATUS_SUCCESS: c_int = 0;
+pub const LIBINPUT_CONFIG_STATUS_UNSUPPORTED: c_int = 1;
+
+// NOTE The libinput enums are passed as `c_int`, the values are converted
+// from the safe types in `types::libinput_config`.
+extern "C" {
+    pub fn libinput_device_config_accel_set_speed(device: *mut libinput_device,
+                                                  speed: c_double)
+                                                  -> c_int;
+
+    pub fn libinput_device_config_accel_set_profile(device: *mut libinput_device,
+                                                    profile: c_int)
+                                                    -> c_int;
+
+    pub fn libinput_device_config_scroll_set_natural_scroll_enabled(device: *mut libinput_device,
+                                                                    enable: c_int)
+                                                                    -> c_int;
+
+    pub fn libinput_device_config_tap_set_enabled(device: *mut libinput_device,
+                                                  enable: c_int)
+                                                  -> c_int;
+
+    pub fn libinput_device_config_tap_set_drag_enabled(device: *mut libinput_device,
+                                                       enable: c_int)
+                                                       -> c_int;
+
+    pub fn libinput_device_config_dwt_set_enabled(device: *mut libinput_device,
+                                                  enable: c_int)
+                                                  -> c_int;
+
+    pub fn libinput_device_config_left_handed_set(device: *mut libinput_device,
+                                                  left_handed: c_int)
+                                                  -> c_int;
+
+    pub fn libinput_device_config_scroll_set_method(device: *mut libinput_device,
+                                                    method: c_int)
+                                                    -> c_int;
+
+    pub fn libinput_device_config_click_set_method(device: *mut libinput_device,
+                                                   method: c_int)
+                                                   -> c_int;
+}
@@ -27,27 +27,44 @@ extern crate bitflags;
 extern crate lazy_static;
 extern crate libc;
 #[macro_use]
+extern crate log;
+#[macro_use]
 extern crate wayland_sys;
 pub extern crate wlroots_sys;
 pub extern crate xkbcommon;
 
 #[macro_use]
 mod macros;
+mod backend;
+mod ffi;
 mod manager;
 mod compositor;
+mod event_loop;
+pub mod logging;
 pub mod events;
 pub mod types;
 pub mod extensions;
 pub mod render;
 mod utils;
+pub mod bindings;
 
 
-pub use self::compositor::{Compositor, CompositorBuilder, terminate};
+pub use self::backend::Backend;
+pub use self::compositor::{Compositor, CompositorBuilder, CompositorError, DisplayExport};
+pub use self::event_loop::{EventLoop, FdEvents, FdSource, SignalSource, TimerSource};
+pub use self::logging::LogVerbosity;
 pub use self::events::key_events::*;
 pub use self::events::pointer_events::*;
-pub use self::manager::{InputManagerHandler, KeyboardHandler, OutputBuilder, OutputBuilderResult,
-                        OutputHandler, OutputManagerHandler, PointerHandler};
+pub use self::events::tablet_pad_events::*;
+pub use self::events::tablet_tool_events::*;
+pub use self::events::touch_events::*;
+pub use self::manager::{ClientManagerHandler, InputManagerHandler, KeyboardHandler, OutputBuilder,
+                        OutputBuilderResult, OutputHandler, OutputManagerHandler, PointerHandler,
+                        TabletPadHandler, TabletToolHandler, TouchHandler};
+pub use self::types::client::*;
 pub use self::types::cursor::*;
 pub use self::types::input_device::*;
 pub use self::types::keyboard::*;
+pub use self::types::keymap::*;
+pub use self::types::libinput_config::*;
 pub use self::types::output::*;
@@ -0,0 +1,78 @@
+//! Forwards the log messages of wlroots to the `log` crate.
+//!
+//! Once `init` is called every message logged by wlroots, as well as by the
+//! `wlr_log!` macro, is turned into a `log` record with the `wlroots` target.
+//! This means any logger (e.g `env_logger`, or one that writes to journald)
+//! captures the messages from both C and Rust.
+//!
+//! `CompositorBuilder::log_verbosity` does this automatically.
+
+use libc::{c_char, size_t};
+use log::LogLevel;
+use std::ffi::CStr;
+use std::panic;
+
+use ffi::{VaList, vsnprintf};
+
+use wlroots_sys::{log_importance_t, wlr_log_init};
+
+/// Messages that are longer than this are truncated.
+const MAX_MESSAGE_LEN: usize = 1024;
+
+/// How verbose wlroots is allowed to be.
+///
+/// Messages that are more verbose are dropped by wlroots before they reach
+/// the logger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogVerbosity {
+    /// Don't log anything.
+    Silent,
+    /// Only log errors.
+    Error,
+    /// Log errors and informational messages.
+    Info,
+    /// Log everything.
+    Debug
+}
+
+impl LogVerbosity {
+    fn as_importance(self) -> log_importance_t {
+        use self::LogVerbosity::*;
+        match self {
+            Silent => log_importance_t::L_SILENT,
+            Error => log_importance_t::L_ERROR,
+            Info => log_importance_t::L_INFO,
+            Debug => log_importance_t::L_DEBUG
+        }
+    }
+}
+
+/// Sends the messages of wlroots to the `log` crate instead of stderr.
+///
+/// Can be called again to change the verbosity.
+pub fn init(verbosity: LogVerbosity) {
+    unsafe { wlr_log_init(verbosity.as_importance(), Some(log_callback)) }
+}
+
+unsafe extern "C" fn log_callback(importance: log_importance_t,
+                                  fmt: *const c_char,
+                                  args: VaList) {
+    use wlroots_sys::log_importance_t::*;
+    let level = match importance {
+        L_ERROR => LogLevel::Error,
+        L_INFO => LogLevel::Info,
+        L_DEBUG => LogLevel::Debug,
+        L_SILENT | L_LAST => return
+    };
+    if !log_enabled!(target: "wlroots", level) {
+        return
+    }
+    let mut buffer = [0 as c_char; MAX_MESSAGE_LEN];
+    if vsnprintf(buffer.as_mut_ptr(), MAX_MESSAGE_LEN as size_t, fmt, args) < 0 {
+        return
+    }
+    let message = CStr::from_ptr(buffer.as_ptr()).to_string_lossy();
+    // NOTE A panicking logger can't be reported through `handle_unwind`,
+    // because that logs the panic, which would call the logger again.
+    let _ = panic::catch_unwind(|| log!(target: "wlroots", level, "{}", message));
+}
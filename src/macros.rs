@@ -59,6 +59,9 @@ macro_rules! wlr_log {
 /// The structure that is defined is repr(C), has one `data` field with the
 /// given user type, and a field for each `$listener`.
 ///
+/// The structure can optionally be generic over some type parameters
+/// (e.g `KeyboardWrapper<T>`), which can then be used in the data type.
+///
 /// Each `$listener` has a getter method that lets you get the pointer to the
 /// listener. This method is unsafe, since it returns a raw pointer.
 /// To use it correctly, you need to ensure that the data it refers to never
@@ -117,19 +120,24 @@ macro_rules! wlr_log {
 ///
 /// Second, this macro doesn't protect against the stored data being unsized.
 /// Passing a pointer of unsized data to C is UB, don't do it.
+///
+/// # Panics
+/// A panic in the body would unwind into C, so it is caught instead.
+/// The compositor returns it as `CompositorError::HandlerPanicked` once it
+/// is back in control.
 #[macro_export]
 macro_rules! wayland_listener {
-    ($struct_name: ident, $data: ty, $([
+    ($struct_name: ident $(<$($generic: ident),*>)*, $data: ty, $([
         $($listener: ident => $listener_func: ident :
           |$($func_arg:ident: $func_type:ty,)*| unsafe $body: block;)*])+) => {
         #[repr(C)]
-        pub struct $struct_name {
+        pub struct $struct_name $(<$($generic),*>)* {
             data: $data,
             $($($listener: $crate::wlroots_sys::wl_listener),*)*
         }
 
-        impl $struct_name {
-            pub fn new(data: $data) -> Box<$struct_name> {
+        impl $(<$($generic),*>)* $struct_name $(<$($generic),*>)* {
+            pub fn new(data: $data) -> Box<Self> {
                 use $crate::wayland_sys::server::WAYLAND_SERVER_HANDLE;
                 Box::new($struct_name {
                     data,
@@ -142,7 +150,7 @@ macro_rules! wayland_listener {
                         ffi_dispatch!(WAYLAND_SERVER_HANDLE,
                                       wl_list_init,
                                       &mut listener.link as *mut _ as _);
-                        ::std::ptr::write(&mut listener.notify, Some($struct_name::$listener_func));
+                        ::std::ptr::write(&mut listener.notify, Some(Self::$listener_func));
                         listener
                     }),*)*
                 })
@@ -156,32 +164,12 @@ macro_rules! wayland_listener {
             $($(pub unsafe extern "C" fn $listener_func(listener:
                                                         *mut $crate::wlroots_sys::wl_listener,
                                                         data: *mut libc::c_void) {
-                let manager: &mut $struct_name = &mut (*container_of!(listener,
-                                                                      $struct_name,
-                                                                      $listener));
-                (|$($func_arg: $func_type,)*| { $body })(manager, data);
+                let manager: &mut Self = &mut (*container_of!(listener, Self, $listener));
+                $crate::utils::handle_unwind(|| {
+                    (|$($func_arg: $func_type,)*| { $body })(manager, data)
+                });
             })*)*
         }
     }
 }
 
-
-/// Used to indicate what data is global compositor data.
-/// It will automatically implement the CompositorData trait for the struct,
-/// and also add a method to `Compositor` to unwrap the data from the fat
-/// pointer.
-#[macro_export]
-macro_rules! compositor_data {
-    ($struct_name: ty) => {
-        impl<'a>::std::convert::From<&'a mut $crate::Compositor> for &'a mut $struct_name {
-            fn from(compositor: &'a mut $crate::Compositor) -> &'a mut $struct_name {
-                &mut *compositor.data.downcast_mut::<$struct_name>()
-                    .unwrap_or_else(|| {
-                        wlr_log!(L_ERROR, "Could not cast compositor state to {:#?}",
-                                 stringify!($struct_name));
-                        panic!("Could not cast compositor state to correct value")
-                    })
-            }
-        }
-    }
-}
@@ -0,0 +1,151 @@
+//! Manager that is called when a client connects or disconnects.
+//! Pass a struct that implements this trait to
+//! `Compositor::set_client_handler`.
+
+use libc;
+
+use compositor::{Compositor, CompositorHandle};
+use types::Client;
+
+use wayland_sys::server::{WAYLAND_SERVER_HANDLE, wl_client};
+
+/// Handles clients connecting and disconnecting.
+///
+/// `T` is the type of the data stored in the `Compositor`.
+pub trait ClientManagerHandler<T = ()> {
+    /// Called when a client connects to the compositor.
+    fn client_created(&mut self, &mut Compositor<T>, &Client) {}
+
+    /// Called when a client disconnects, or is disconnected.
+    ///
+    /// The client is no longer alive at this point.
+    fn client_destroyed(&mut self, &mut Compositor<T>, &Client) {}
+}
+
+wayland_listener!(ClientManager<T>, (Vec<Box<ClientWrapper<T>>>,
+                                     Option<Box<ClientManagerHandler<T>>>,
+                                     CompositorHandle<T>), [
+    created_listener => created_notify: |this: &mut ClientManager<T>, data: *mut libc::c_void,|
+    unsafe {
+        let manager = this as *mut ClientManager<T>;
+        let (ref mut clients, _, _) = this.data;
+        let client_ptr = data as *mut wl_client;
+        let client = Client::from_ptr(client_ptr);
+        let mut wrapper = ClientWrapper::new((client.clone(), manager));
+        ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                      wl_client_add_destroy_listener,
+                      client_ptr,
+                      wrapper.destroy_listener() as *mut _ as _);
+        // Store the listener, free later in the destroy listener
+        clients.push(wrapper);
+        ClientManager::call_handler(manager, |handler, compositor| {
+            handler.client_created(compositor, &client)
+        })
+    };
+]);
+
+// NOTE The manager is boxed by the compositor, so the pointer to it is stable.
+wayland_listener!(ClientWrapper<T>, (Client, *mut ClientManager<T>), [
+    destroy_listener => destroy_notify: |this: &mut ClientWrapper<T>, _data: *mut libc::c_void,|
+    unsafe {
+        let client = this.data.0.clone();
+        let manager = this.data.1;
+        client.destroyed();
+        // NOTE This frees `this`, so it can't be used past this point.
+        (*manager).remove(&client);
+        ClientManager::call_handler(manager, |handler, compositor| {
+            handler.client_destroyed(compositor, &client)
+        })
+    };
+]);
+
+impl<T> ClientManager<T> {
+    /// Gets the clients that are currently connected.
+    pub(crate) fn clients(&self) -> Vec<Client> {
+        self.data.0.iter().map(|wrapper| wrapper.data.0.clone()).collect()
+    }
+
+    /// Gets the client with the given pointer, if it's connected.
+    pub(crate) fn find(&self, client: *mut wl_client) -> Option<Client> {
+        self.data.0.iter()
+            .map(|wrapper| &wrapper.data.0)
+            .find(|other| unsafe { other.as_ptr() } == client)
+            .cloned()
+    }
+
+    pub(crate) fn set_handler(&mut self, handler: Box<ClientManagerHandler<T>>) {
+        self.data.1 = Some(handler)
+    }
+
+    /// Calls the handler with the compositor, if there are both.
+    ///
+    /// While it runs, events the handler causes itself (e.g by disconnecting
+    /// a client) are not reported to it.
+    unsafe fn call_handler<F>(manager: *mut ClientManager<T>, func: F)
+        where F: FnOnce(&mut ClientManagerHandler<T>, &mut Compositor<T>)
+    {
+        let compositor = match (*manager).data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        with_handler(&mut (*manager).data.1, |handler| func(handler, compositor))
+    }
+
+    fn remove(&mut self, client: &Client) {
+        if let Some(index) = self.data.0.iter().position(|wrapper| wrapper.data.0 == *client) {
+            let mut removed = self.data.0.remove(index);
+            unsafe {
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                              wl_list_remove,
+                              &mut (*removed.destroy_listener()).link as *mut _ as _);
+            }
+        }
+    }
+}
+
+/// Runs `func` with the handler taken out of `slot`, then puts it back
+/// unless `func` stored a new one.
+///
+/// NOTE The handler can be replaced while it runs (e.g through
+/// `Compositor::set_client_handler`), which would free it under its own
+/// feet if it was left in `slot`.
+unsafe fn with_handler<H: ?Sized, F>(slot: *mut Option<Box<H>>, func: F)
+    where F: FnOnce(&mut H)
+{
+    let mut handler = match (*slot).take() {
+        Some(handler) => handler,
+        None => return
+    };
+    func(&mut *handler);
+    if (*slot).is_none() {
+        *slot = Some(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::with_handler;
+
+    #[test]
+    fn handler_is_put_back() {
+        let mut slot = Some(Box::new(vec![1]));
+        unsafe { with_handler(&mut slot, |handler| handler.push(2)) }
+        assert_eq!(slot, Some(Box::new(vec![1, 2])));
+    }
+
+    #[test]
+    fn handler_replaced_while_running() {
+        let mut slot = Some(Box::new(vec![1]));
+        let slot_ptr = &mut slot as *mut Option<Box<Vec<i32>>>;
+        unsafe {
+            with_handler(slot_ptr, |handler| {
+                // Like calling `set_client_handler` from `client_created`.
+                *slot_ptr = Some(Box::new(vec![2]));
+                // The old handler is still usable until it returns.
+                handler.push(3);
+                assert_eq!(*handler, vec![1, 3]);
+            })
+        }
+        assert_eq!(slot, Some(Box::new(vec![2])));
+    }
+}
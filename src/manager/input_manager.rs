@@ -4,75 +4,134 @@
 
 use libc;
 
-use std::env;
 use std::process::abort;
 
-use super::{KeyboardHandler, KeyboardWrapper, PointerHandler, PointerWrapper};
-use compositor::{COMPOSITOR_PTR, Compositor};
-use types::{InputDevice, KeyboardHandle, PointerHandle};
-use utils::safe_as_cstring;
+use super::{KeyboardHandler, KeyboardWrapper, PointerHandler, PointerWrapper, TabletPadHandler,
+            TabletPadWrapper, TabletToolHandler, TabletToolWrapper, TouchHandler, TouchWrapper};
+use compositor::{Compositor, CompositorHandle};
+use types::{InputDevice, KeyboardHandle, PointerHandle, TabletPadHandle, TabletToolHandle,
+            TouchHandle};
 
 use wayland_sys::server::WAYLAND_SERVER_HANDLE;
 use wayland_sys::server::signal::wl_signal_add;
-use wlroots_sys::{wlr_input_device, wlr_input_device_type, wlr_keyboard_set_keymap,
-                  xkb_context_new, xkb_context_unref, xkb_keymap_new_from_names, xkb_rule_names};
-use wlroots_sys::xkb_context_flags::*;
-use wlroots_sys::xkb_keymap_compile_flags::*;
+use wlroots_sys::{wlr_input_device, wlr_input_device_type};
 
 /// Different type of inputs that can be acquired.
-pub enum Input {
-    Keyboard(Box<KeyboardWrapper>),
-    Pointer(Box<PointerWrapper>)
+pub enum Input<T> {
+    Keyboard(Box<KeyboardWrapper<T>>),
+    Pointer(Box<PointerWrapper<T>>),
+    Touch(Box<TouchWrapper<T>>),
+    TabletTool(Box<TabletToolWrapper<T>>),
+    TabletPad(Box<TabletPadWrapper<T>>)
 }
 
-impl Input {
+impl<T> Input<T> {
     pub unsafe fn input_device(&self) -> *mut wlr_input_device {
         use self::Input::*;
         match *self {
             Keyboard(ref keyboard) => keyboard.input_device(),
             Pointer(ref pointer) => pointer.input_device(),
+            Touch(ref touch) => touch.input_device(),
+            TabletTool(ref tablet_tool) => tablet_tool.input_device(),
+            TabletPad(ref tablet_pad) => tablet_pad.input_device()
         }
     }
 }
 
 /// Handles input addition and removal.
-pub trait InputManagerHandler {
+///
+/// `T` is the type of the data stored in the `Compositor`.
+pub trait InputManagerHandler<T = ()> {
     /// Callback triggered when an input device is added.
-    fn input_added(&mut self, &mut Compositor, &mut InputDevice) {}
+    fn input_added(&mut self, &mut Compositor<T>, &mut InputDevice) {}
 
     /// Callback triggered when an input device is removed.
-    fn input_removed(&mut self, &mut Compositor, &mut InputDevice) {
-        // TODO
-    }
+    ///
+    /// This is called before the typed callback of the device
+    /// (e.g `keyboard_removed`).
+    fn input_removed(&mut self, &mut Compositor<T>, &mut InputDevice) {}
 
+    /// Callback triggered when a keyboard is added.
+    ///
+    /// The keyboard already has the default keymap of the compositor, unless
+    /// it could not be compiled. Use `KeyboardHandle::set_keymap` to give it
+    /// another one.
     fn keyboard_added(&mut self,
-                      &mut Compositor,
+                      &mut Compositor<T>,
                       &mut KeyboardHandle)
-                      -> Option<Box<KeyboardHandler>> {
+                      -> Option<Box<KeyboardHandler<T>>> {
         None
     }
 
+    /// Callback triggered when a keyboard that has a handler is removed.
+    ///
+    /// The handler given by `keyboard_added` is handed back, so that any
+    /// state of the keyboard can be torn down. It no longer gets events and
+    /// is dropped if it's not kept.
+    fn keyboard_removed(&mut self,
+                        &mut Compositor<T>,
+                        &mut KeyboardHandle,
+                        Box<KeyboardHandler<T>>) {
+    }
+
     fn pointer_added(&mut self,
-                     &mut Compositor,
+                     &mut Compositor<T>,
                      &mut PointerHandle)
-                     -> Option<Box<PointerHandler>> {
+                     -> Option<Box<PointerHandler<T>>> {
+        None
+    }
+
+    /// Callback triggered when a pointer that has a handler is removed.
+    ///
+    /// The handler given by `pointer_added` is handed back, so that any state
+    /// of the pointer (e.g its attachment to a cursor) can be torn down. It
+    /// no longer gets events and is dropped if it's not kept.
+    fn pointer_removed(&mut self,
+                       &mut Compositor<T>,
+                       &mut PointerHandle,
+                       Box<PointerHandler<T>>) {
+    }
+
+    /// Callback triggered when a touch screen is added.
+    fn touch_added(&mut self,
+                   &mut Compositor<T>,
+                   &mut TouchHandle)
+                   -> Option<Box<TouchHandler<T>>> {
+        None
+    }
+
+    /// Callback triggered when a tablet tool (e.g a pen) is added.
+    fn tablet_tool_added(&mut self,
+                         &mut Compositor<T>,
+                         &mut TabletToolHandle)
+                         -> Option<Box<TabletToolHandler<T>>> {
+        None
+    }
+
+    /// Callback triggered when the pad of a tablet is added.
+    fn tablet_pad_added(&mut self,
+                        &mut Compositor<T>,
+                        &mut TabletPadHandle)
+                        -> Option<Box<TabletPadHandler<T>>> {
         None
     }
 }
 
-wayland_listener!(InputManager, (Vec<Input>, Box<InputManagerHandler>), [
-    add_listener => add_notify: |this: &mut InputManager, data: *mut libc::c_void,| unsafe {
+wayland_listener!(InputManager<T>, (Vec<Input<T>>,
+                                    Box<InputManagerHandler<T>>,
+                                    CompositorHandle<T>), [
+    add_listener => add_notify: |this: &mut InputManager<T>, data: *mut libc::c_void,| unsafe {
         let data = data as *mut wlr_input_device;
-        let (ref mut inputs, ref mut manager) = this.data;
+        let (ref mut inputs, ref mut manager, ref handle) = this.data;
         use self::wlr_input_device_type::*;
         let mut dev = InputDevice::from_ptr(data);
-        let compositor = &mut *COMPOSITOR_PTR;
+        let compositor = match handle.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
         unsafe {
             match dev.dev_type() {
                 WLR_INPUT_DEVICE_KEYBOARD => {
-                    // Boring setup that we won't make the user do
-                    add_keyboard(&mut dev);
-                    // Get the optional user keyboard struct, add the on_key signal
                     let mut keyboard_handle = match KeyboardHandle::from_input_device(data) {
                         Some(dev) => dev,
                         None => {
@@ -80,12 +139,29 @@ wayland_listener!(InputManager, (Vec<Input>, Box<InputManagerHandler>), [
                             abort()
                         }
                     };
+                    // Boring setup that we won't make the user do,
+                    // they can still pick another keymap in keyboard_added.
+                    match compositor.keymap() {
+                        Ok(keymap) => keyboard_handle.set_keymap(keymap),
+                        Err(err) => wlr_log!(L_ERROR, "Could not compile default keymap: {}", err)
+                    }
+                    compositor.add_key_repeat(keyboard_handle.repeat());
+                    // Get the optional user keyboard struct, add the on_key signal
                     if let Some(keyboard_handler) = manager.keyboard_added(compositor,
                                                                            &mut keyboard_handle) {
                         let mut keyboard = KeyboardWrapper::new((keyboard_handle,
-                                                                 keyboard_handler));
-                        wl_signal_add(&mut (*dev.dev_union().keyboard).events.key as *mut _ as _,
-                                    keyboard.key_listener() as *mut _ as _);
+                                                                 keyboard_handler,
+                                                                 handle.clone(),
+                                                                 None));
+                        let keyboard_ptr = dev.dev_union().keyboard;
+                        wl_signal_add(&mut (*keyboard_ptr).events.key as *mut _ as _,
+                                      keyboard.key_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*keyboard_ptr).events.modifiers as *mut _ as _,
+                                      keyboard.modifiers_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*keyboard_ptr).events.keymap as *mut _ as _,
+                                      keyboard.keymap_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*keyboard_ptr).events.repeat_info as *mut _ as _,
+                                      keyboard.repeat_info_listener() as *mut _ as _);
                         // Forget until we need to drop it in the destroy callback
                         inputs.push(Input::Keyboard(keyboard));
                     }
@@ -100,7 +176,9 @@ wayland_listener!(InputManager, (Vec<Input>, Box<InputManagerHandler>), [
                         }
                     };
                     if let Some(pointer) = manager.pointer_added(compositor, &mut pointer_handle) {
-                        let mut pointer = PointerWrapper::new((pointer_handle, pointer));
+                        let mut pointer = PointerWrapper::new((pointer_handle,
+                                                               pointer,
+                                                               handle.clone()));
                         wl_signal_add(&mut (*dev.dev_union().pointer).events.motion as *mut _ as _,
                                     pointer.motion_listener() as *mut _ as _);
                         wl_signal_add(&mut (*dev.dev_union().pointer)
@@ -114,16 +192,91 @@ wayland_listener!(InputManager, (Vec<Input>, Box<InputManagerHandler>), [
                         inputs.push(Input::Pointer(pointer))
                     }
                 },
-                _ => unimplemented!(), // TODO FIXME We _really_ shouldn't panic here
+                WLR_INPUT_DEVICE_TOUCH => {
+                    let mut touch_handle = match TouchHandle::from_input_device(data) {
+                        Some(dev) => dev,
+                        None => {
+                            wlr_log!(L_ERROR, "Device {:#?} was not a touch screen!", dev);
+                            abort()
+                        }
+                    };
+                    if let Some(touch) = manager.touch_added(compositor, &mut touch_handle) {
+                        let mut touch = TouchWrapper::new((touch_handle, touch, handle.clone()));
+                        let touch_ptr = dev.dev_union().touch;
+                        wl_signal_add(&mut (*touch_ptr).events.down as *mut _ as _,
+                                      touch.down_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*touch_ptr).events.up as *mut _ as _,
+                                      touch.up_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*touch_ptr).events.motion as *mut _ as _,
+                                      touch.motion_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*touch_ptr).events.cancel as *mut _ as _,
+                                      touch.cancel_listener() as *mut _ as _);
+                        // Forget until we need to drop it in the destroy callback
+                        inputs.push(Input::Touch(touch))
+                    }
+                },
+                WLR_INPUT_DEVICE_TABLET_TOOL => {
+                    let mut tablet_tool_handle =
+                        match TabletToolHandle::from_input_device(data) {
+                            Some(dev) => dev,
+                            None => {
+                                wlr_log!(L_ERROR, "Device {:#?} was not a tablet tool!", dev);
+                                abort()
+                            }
+                        };
+                    if let Some(tablet_tool) = manager.tablet_tool_added(compositor,
+                                                                         &mut tablet_tool_handle) {
+                        let mut tablet_tool = TabletToolWrapper::new((tablet_tool_handle,
+                                                                      tablet_tool,
+                                                                      handle.clone()));
+                        let tool_ptr = dev.dev_union().tablet_tool;
+                        wl_signal_add(&mut (*tool_ptr).events.axis as *mut _ as _,
+                                      tablet_tool.axis_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*tool_ptr).events.proximity as *mut _ as _,
+                                      tablet_tool.proximity_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*tool_ptr).events.tip as *mut _ as _,
+                                      tablet_tool.tip_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*tool_ptr).events.button as *mut _ as _,
+                                      tablet_tool.button_listener() as *mut _ as _);
+                        // Forget until we need to drop it in the destroy callback
+                        inputs.push(Input::TabletTool(tablet_tool))
+                    }
+                },
+                WLR_INPUT_DEVICE_TABLET_PAD => {
+                    let mut tablet_pad_handle = match TabletPadHandle::from_input_device(data) {
+                        Some(dev) => dev,
+                        None => {
+                            wlr_log!(L_ERROR, "Device {:#?} was not a tablet pad!", dev);
+                            abort()
+                        }
+                    };
+                    if let Some(tablet_pad) = manager.tablet_pad_added(compositor,
+                                                                       &mut tablet_pad_handle) {
+                        let mut tablet_pad = TabletPadWrapper::new((tablet_pad_handle,
+                                                                    tablet_pad,
+                                                                    handle.clone()));
+                        let pad_ptr = dev.dev_union().tablet_pad;
+                        wl_signal_add(&mut (*pad_ptr).events.button as *mut _ as _,
+                                      tablet_pad.button_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*pad_ptr).events.ring as *mut _ as _,
+                                      tablet_pad.ring_listener() as *mut _ as _);
+                        wl_signal_add(&mut (*pad_ptr).events.strip as *mut _ as _,
+                                      tablet_pad.strip_listener() as *mut _ as _);
+                        // Forget until we need to drop it in the destroy callback
+                        inputs.push(Input::TabletPad(tablet_pad))
+                    }
+                }
             }
         }
         manager.input_added(compositor, &mut dev)
     };
-    remove_listener => remove_notify: |this: &mut InputManager, data: *mut libc::c_void,| unsafe {
+    remove_listener => remove_notify: |this: &mut InputManager<T>, data: *mut libc::c_void,|
+    unsafe {
         let data = data as *mut wlr_input_device;
-        let (ref mut inputs, ref mut manager) = this.data;
-        let compositor = &mut *COMPOSITOR_PTR;
-        manager.input_removed(compositor, &mut InputDevice::from_ptr(data));
+        let (ref mut inputs, ref mut manager, ref handle) = this.data;
+        if let Some(compositor) = handle.get() {
+            manager.input_removed(compositor, &mut InputDevice::from_ptr(data));
+        }
         // Remove user output data
         let find_index = inputs.iter()
             .position(|input| input.input_device() == data);
@@ -134,6 +287,20 @@ wayland_listener!(InputManager, (Vec<Input>, Box<InputManagerHandler>), [
                     ffi_dispatch!(WAYLAND_SERVER_HANDLE,
                                   wl_list_remove,
                                   &mut (*keyboard.key_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*keyboard.modifiers_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*keyboard.keymap_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*keyboard.repeat_info_listener()).link as *mut _ as _);
+                    let (mut keyboard, keyboard_handler) = keyboard.into_parts();
+                    if let Some(compositor) = handle.get() {
+                        compositor.forget_keyboard(keyboard.to_ptr());
+                        manager.keyboard_removed(compositor, &mut keyboard, keyboard_handler)
+                    }
                 },
                 Input::Pointer(mut pointer) => {
                     ffi_dispatch!(WAYLAND_SERVER_HANDLE,
@@ -148,33 +315,51 @@ wayland_listener!(InputManager, (Vec<Input>, Box<InputManagerHandler>), [
                     ffi_dispatch!(WAYLAND_SERVER_HANDLE,
                                   wl_list_remove,
                                   &mut (*pointer.axis_listener()).link as *mut _ as _);
+                    let (mut pointer, pointer_handler) = pointer.into_parts();
+                    if let Some(compositor) = handle.get() {
+                        manager.pointer_removed(compositor, &mut pointer, pointer_handler)
+                    }
+                },
+                Input::Touch(mut touch) => {
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*touch.down_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*touch.up_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*touch.motion_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*touch.cancel_listener()).link as *mut _ as _);
+                },
+                Input::TabletTool(mut tablet_tool) => {
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*tablet_tool.axis_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*tablet_tool.proximity_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*tablet_tool.tip_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*tablet_tool.button_listener()).link as *mut _ as _);
+                },
+                Input::TabletPad(mut tablet_pad) => {
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*tablet_pad.button_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*tablet_pad.ring_listener()).link as *mut _ as _);
+                    ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                                  wl_list_remove,
+                                  &mut (*tablet_pad.strip_listener()).link as *mut _ as _);
                 }
             }
         }
     };
 ]);
-
-pub unsafe fn add_keyboard(dev: &mut InputDevice) {
-    // Set the XKB settings
-    let rules = safe_as_cstring(env::var("XKB_DEFAULT_RULES").unwrap_or("".into()));
-    let model = safe_as_cstring(env::var("XKB_DEFAULT_MODEL").unwrap_or("".into()));
-    let layout = safe_as_cstring(env::var("XKB_DEFAULT_LAYOUT").unwrap_or("".into()));
-    let variant = safe_as_cstring(env::var("XKB_DEFAULT_VARIANT").unwrap_or("".into()));
-    let options = safe_as_cstring(env::var("XKB_DEFAULT_OPTIONS").unwrap_or("".into()));
-    let rules = xkb_rule_names {
-        rules: rules.into_raw(),
-        model: model.into_raw(),
-        layout: layout.into_raw(),
-        variant: variant.into_raw(),
-        options: options.into_raw()
-    };
-    let context = xkb_context_new(XKB_CONTEXT_NO_FLAGS);
-    if context.is_null() {
-        wlr_log!(L_ERROR, "Failed to create XKB context");
-        // NOTE We don't panic here, because we have a C call stack above us
-        ::std::process::exit(1)
-    }
-    let xkb_map = xkb_keymap_new_from_names(context, &rules, XKB_KEYMAP_COMPILE_NO_FLAGS);
-    wlr_keyboard_set_keymap(dev.dev_union().keyboard, xkb_map);
-    xkb_context_unref(context);
-}
@@ -1,31 +1,179 @@
 //! Handler for keyboards
 
 use libc;
+use std::mem;
+use std::time::Duration;
 
-use compositor::{COMPOSITOR_PTR, Compositor};
+use compositor::{Compositor, CompositorHandle};
+use event_loop::TimerSource;
 use events::key_events::KeyEvent;
 use types::KeyboardHandle;
 
-use wlroots_sys::{wlr_event_keyboard_key, wlr_input_device};
+use wlroots_sys::{wlr_event_keyboard_key, wlr_input_device, xkb_keymap_key_repeats};
+use wlroots_sys::wlr_key_state::*;
 
-pub trait KeyboardHandler {
-    /// Callback that is triggered when a key is pressed.
-    fn on_key(&mut self, &mut Compositor, &mut KeyboardHandle, &mut KeyEvent) {}
+pub trait KeyboardHandler<T = ()> {
+    /// Callback that is triggered when a key is pressed or released, or
+    /// repeated when `KeyboardHandle::set_repeat_enabled` is used.
+    fn on_key(&mut self, &mut Compositor<T>, &mut KeyboardHandle, &mut KeyEvent) {}
+
+    /// Callback that is triggered when the modifiers of the keyboard change.
+    ///
+    /// Use `KeyboardHandle::get_modifiers` to get the new modifiers.
+    fn on_modifiers(&mut self, &mut Compositor<T>, &mut KeyboardHandle) {}
+
+    /// Callback that is triggered when the keyboard gets a new keymap.
+    fn on_keymap(&mut self, &mut Compositor<T>, &mut KeyboardHandle) {}
+
+    /// Callback that is triggered when the repeat rate or delay of the
+    /// keyboard changes.
+    ///
+    /// Use `KeyboardHandle::repeat_info` to get the new values.
+    fn on_repeat_info(&mut self, &mut Compositor<T>, &mut KeyboardHandle) {}
 }
 
-wayland_listener!(KeyboardWrapper, (KeyboardHandle, Box<KeyboardHandler>), [
-    key_listener => key_notify: |this: &mut KeyboardWrapper, data: *mut libc::c_void,| unsafe {
-        let (ref mut keyboard, ref mut keyboard_handler) = this.data;
-        let compositor = &mut *COMPOSITOR_PTR;
-        let xkb_state = (*keyboard.to_ptr()).xkb_state;
-        let mut key = KeyEvent::new(data as *mut wlr_event_keyboard_key, xkb_state);
+wayland_listener!(KeyboardWrapper<T>, (KeyboardHandle,
+                                       Box<KeyboardHandler<T>>,
+                                       CompositorHandle<T>,
+                                       // Created the first time a key repeats
+                                       Option<TimerSource<T>>), [
+    key_listener => key_notify: |this: &mut KeyboardWrapper<T>, data: *mut libc::c_void,| unsafe {
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        let event = data as *mut wlr_event_keyboard_key;
+        // NOTE Done before the callback, so that the handler can cancel it.
+        this.update_repeat(compositor, *event);
+        let (ref mut keyboard, ref mut keyboard_handler, _, _) = this.data;
+        compositor.set_active_keyboard(keyboard.to_ptr());
+        let mut key = KeyEvent::new(event, keyboard.to_ptr(), false);
 
         keyboard_handler.on_key(compositor, keyboard, &mut key)
     };
+    modifiers_listener => modifiers_notify: |this: &mut KeyboardWrapper<T>,
+                                             _data: *mut libc::c_void,|
+    unsafe {
+        let (ref mut keyboard, ref mut keyboard_handler, ref handle, _) = this.data;
+        let compositor = match handle.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        compositor.set_active_keyboard(keyboard.to_ptr());
+
+        keyboard_handler.on_modifiers(compositor, keyboard)
+    };
+    keymap_listener => keymap_notify: |this: &mut KeyboardWrapper<T>, _data: *mut libc::c_void,|
+    unsafe {
+        let (ref mut keyboard, ref mut keyboard_handler, ref handle, _) = this.data;
+        let compositor = match handle.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+
+        keyboard_handler.on_keymap(compositor, keyboard)
+    };
+    repeat_info_listener => repeat_info_notify: |this: &mut KeyboardWrapper<T>,
+                                                 _data: *mut libc::c_void,|
+    unsafe {
+        let (ref mut keyboard, ref mut keyboard_handler, ref handle, _) = this.data;
+        let compositor = match handle.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+
+        keyboard_handler.on_repeat_info(compositor, keyboard)
+    };
 ]);
 
-impl KeyboardWrapper {
+impl<T> KeyboardWrapper<T> {
     pub unsafe fn input_device(&self) -> *mut wlr_input_device {
         self.data.0.input_device()
     }
+
+    /// Frees the wrapper, giving back the keyboard and its handler.
+    ///
+    /// This stops the key repeat of the keyboard.
+    pub(crate) fn into_parts(self: Box<Self>) -> (KeyboardHandle, Box<KeyboardHandler<T>>) {
+        let (keyboard, keyboard_handler, _, _) = self.data;
+        (keyboard, keyboard_handler)
+    }
+
+    /// Starts repeating a key that was pressed, or stops repeating it once
+    /// it's released.
+    unsafe fn update_repeat(&mut self,
+                            compositor: &mut Compositor<T>,
+                            event: wlr_event_keyboard_key) {
+        // NOTE Type erased, the timer callback must be `'static` and `T` might not be.
+        let wrapper = self as *mut KeyboardWrapper<T> as *mut libc::c_void;
+        let (ref mut keyboard, _, _, ref mut repeat_timer) = self.data;
+        match event.state {
+            WLR_KEY_RELEASED => {
+                if keyboard.repeat().key().map(|key| key.keycode) == Some(event.keycode) {
+                    keyboard.repeat().cancel()
+                }
+            }
+            WLR_KEY_PRESSED => {
+                let keymap = (*keyboard.to_ptr()).keymap;
+                let (rate, delay) = keyboard.repeat_info();
+                // Keys that don't repeat (e.g modifiers) leave the held key repeating.
+                if !keyboard.repeat_enabled() || rate <= 0 || keymap.is_null() ||
+                   xkb_keymap_key_repeats(keymap, event.keycode + 8) == 0 {
+                    return
+                }
+                if repeat_timer.is_none() {
+                    // NOTE The wrapper is boxed and owns the timer,
+                    // so it outlives it.
+                    *repeat_timer = compositor.event_loop().add_timer(move |compositor| {
+                        let wrapper = wrapper as *mut KeyboardWrapper<T>;
+                        (*wrapper).repeat(compositor)
+                    });
+                    if let Some(ref timer) = *repeat_timer {
+                        keyboard.repeat().set_timer(timer.handle())
+                    }
+                }
+                if let Some(ref mut timer) = *repeat_timer {
+                    keyboard.repeat().start(event);
+                    timer.update(Duration::from_millis(delay.max(1) as u64))
+                }
+            }
+        }
+    }
+
+    /// Sends the held key to the handler again, then waits for the next
+    /// repeat.
+    unsafe fn repeat(&mut self, compositor: &mut Compositor<T>) {
+        let (ref mut keyboard, ref mut keyboard_handler, _, ref mut repeat_timer) = self.data;
+        // NOTE Kept outside of the keyboard, the handler can cancel the
+        // repeat through the compositor while the keyboard is borrowed.
+        let repeat = keyboard.repeat().clone();
+        let mut event = match repeat.key() {
+            Some(event) => event,
+            None => return
+        };
+        let (rate, _) = keyboard.repeat_info();
+        if rate <= 0 {
+            repeat.cancel();
+            return
+        }
+        event.time_msec = monotonic_msec();
+        let mut key = KeyEvent::new(&mut event, keyboard.to_ptr(), true);
+        keyboard_handler.on_key(compositor, keyboard, &mut key);
+        // The handler might have cancelled the repeat.
+        if repeat.key().is_some() {
+            if let Some(ref mut timer) = *repeat_timer {
+                timer.update(Duration::from_millis((1000 / rate).max(1) as u64))
+            }
+        }
+    }
+}
+
+/// Gets the time of the monotonic clock in milliseconds, which is the clock
+/// the time of input events comes from.
+fn monotonic_msec() -> u32 {
+    unsafe {
+        let mut now: libc::timespec = mem::zeroed();
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now);
+        (now.tv_sec as u64 * 1000 + now.tv_nsec as u64 / 1_000_000) as u32
+    }
 }
@@ -1,12 +1,20 @@
+mod client_manager;
 mod input_manager;
 mod output_manager;
 mod keyboard_handler;
 mod pointer_handler;
 mod output_handler;
+mod tablet_pad_handler;
+mod tablet_tool_handler;
+mod touch_handler;
 
+pub use self::client_manager::{ClientManager, ClientManagerHandler};
 pub use self::input_manager::{InputManager, InputManagerHandler};
 pub use self::keyboard_handler::{KeyboardHandler, KeyboardWrapper};
 pub use self::output_handler::{OutputHandler, UserOutput};
 pub use self::output_manager::{OutputBuilder, OutputBuilderResult, OutputManager,
                                OutputManagerHandler};
 pub use self::pointer_handler::{PointerHandler, PointerWrapper};
+pub use self::tablet_pad_handler::{TabletPadHandler, TabletPadWrapper};
+pub use self::tablet_tool_handler::{TabletToolHandler, TabletToolWrapper};
+pub use self::touch_handler::{TouchHandler, TouchWrapper};
@@ -1,32 +1,35 @@
 //! Handler for outputs
 
-use compositor::{COMPOSITOR_PTR, Compositor};
+use compositor::{Compositor, CompositorHandle};
 use libc;
 use types::OutputHandle;
 use wlroots_sys::wlr_output;
 
-pub trait OutputHandler {
+pub trait OutputHandler<T = ()> {
     /// Called every time the output frame is updated.
-    fn output_frame(&mut self, &mut Compositor, &mut OutputHandle) {}
+    fn output_frame(&mut self, &mut Compositor<T>, &mut OutputHandle) {}
 
     /// Called every time the output resolution changes.
     fn output_resolution(&mut self, &mut OutputHandle) {}
 }
 
-wayland_listener!(UserOutput, (*mut wlr_output, Box<OutputHandler>), [
-    frame_listener => frame_notify: |this: &mut UserOutput, data: *mut libc::c_void,| unsafe {
-        let manager = &mut this.data.1;
-        let output = &mut *COMPOSITOR_PTR;
-        manager.output_frame(output, &mut OutputHandle::from_ptr(data as *mut wlr_output))
+wayland_listener!(UserOutput<T>, (*mut wlr_output, Box<OutputHandler<T>>, CompositorHandle<T>), [
+    frame_listener => frame_notify: |this: &mut UserOutput<T>, data: *mut libc::c_void,| unsafe {
+        let (_, ref mut manager, ref handle) = this.data;
+        let compositor = match handle.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        manager.output_frame(compositor, &mut OutputHandle::from_ptr(data as *mut wlr_output))
     };
-    resolution_listener => resolution_notify: |this: &mut UserOutput, data: *mut libc::c_void,|
+    resolution_listener => resolution_notify: |this: &mut UserOutput<T>, data: *mut libc::c_void,|
     unsafe {
         let manager = &mut this.data.1;
         manager.output_resolution(&mut OutputHandle::from_ptr(data as *mut wlr_output))
     };
 ]);
 
-impl UserOutput {
+impl<T> UserOutput<T> {
     pub unsafe fn output_ptr(&self) -> *mut wlr_output {
         self.data.0
     }
@@ -3,7 +3,7 @@
 //! initialization.
 
 
-use compositor::{COMPOSITOR_PTR, Compositor};
+use compositor::{Compositor, CompositorHandle};
 use libc;
 use manager::{OutputHandler, UserOutput};
 use types::OutputHandle;
@@ -20,9 +20,9 @@ pub struct OutputBuilder<'output> {
 
 /// Used to ensure that the builder is used to construct
 /// the OutputHandler instance.
-pub struct OutputBuilderResult<'output> {
+pub struct OutputBuilderResult<'output, T = ()> {
     pub output: &'output mut OutputHandle,
-    result: Box<OutputHandler>
+    result: Box<OutputHandler<T>>
 }
 
 /// Wrapper around Output destruction so that you can't call
@@ -30,26 +30,28 @@ pub struct OutputBuilderResult<'output> {
 pub struct OutputDestruction<'output>(&'output mut OutputHandle);
 
 /// Handles output addition and removal.
-pub trait OutputManagerHandler {
+///
+/// `T` is the type of the data stored in the `Compositor`.
+pub trait OutputManagerHandler<T = ()> {
     /// Called whenever an output is added.
     fn output_added<'output>(&mut self,
-                             &mut Compositor,
+                             &mut Compositor<T>,
                              _: OutputBuilder<'output>)
-                             -> Option<OutputBuilderResult<'output>> {
+                             -> Option<OutputBuilderResult<'output, T>> {
         None
     }
 
     /// Called whenever an output is removed.
-    fn output_removed(&mut self, &mut Compositor, OutputDestruction) {
+    fn output_removed(&mut self, &mut Compositor<T>, OutputDestruction) {
         // TODO
     }
 }
 
 
 impl<'output> OutputBuilder<'output> {
-    pub fn build_best_mode<T: OutputHandler + 'static>(self,
-                                                       data: T)
-                                                       -> OutputBuilderResult<'output> {
+    pub fn build_best_mode<T, H: OutputHandler<T> + 'static>(self,
+                                                             data: H)
+                                                             -> OutputBuilderResult<'output, T> {
         self.output.choose_best_mode();
         OutputBuilderResult {
             output: self.output,
@@ -62,16 +64,21 @@ impl<'output> OutputDestruction<'output> {
     // TODO Functions which are safe to use
 }
 
-wayland_listener!(OutputManager, (Vec<Box<UserOutput>>, Box<OutputManagerHandler>), [
-    add_listener => add_notify: |this: &mut OutputManager, data: *mut libc::c_void,| unsafe {
-        let (ref mut outputs, ref mut manager) = this.data;
+wayland_listener!(OutputManager<T>, (Vec<Box<UserOutput<T>>>,
+                                      Box<OutputManagerHandler<T>>,
+                                      CompositorHandle<T>), [
+    add_listener => add_notify: |this: &mut OutputManager<T>, data: *mut libc::c_void,| unsafe {
+        let (ref mut outputs, ref mut manager, ref handle) = this.data;
         let data = data as *mut wlr_output;
         let mut output = OutputHandle::from_ptr(data as *mut wlr_output);
         let builder = OutputBuilder { output: &mut output };
-        let compositor = &mut *COMPOSITOR_PTR;
+        let compositor = match handle.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
         if let Some(OutputBuilderResult {result: output, ..}) = manager.output_added(compositor,
                                                                                      builder) {
-            let mut output = UserOutput::new((data, output));
+            let mut output = UserOutput::new((data, output, handle.clone()));
             // Add the output frame event to this manager
             wl_signal_add(&mut (*data).events.frame as *mut _ as _,
                         output.frame_listener() as _);
@@ -82,12 +89,14 @@ wayland_listener!(OutputManager, (Vec<Box<UserOutput>>, Box<OutputManagerHandler
             outputs.push(output);
         }
     };
-    remove_listener => remove_notify: |this: &mut OutputManager, data: *mut libc::c_void,| unsafe {
-        let (ref mut outputs, ref mut manager) = this.data;
+    remove_listener => remove_notify: |this: &mut OutputManager<T>, data: *mut libc::c_void,|
+    unsafe {
+        let (ref mut outputs, ref mut manager, ref handle) = this.data;
         let data = data as *mut wlr_output;
         let mut output = OutputHandle::from_ptr(data);
-        let compositor = &mut *COMPOSITOR_PTR;
-        manager.output_removed(compositor, OutputDestruction(&mut output));
+        if let Some(compositor) = handle.get() {
+            manager.output_removed(compositor, OutputDestruction(&mut output));
+        }
         if let Some(layout) = output.layout() {
             layout.borrow_mut().remove(&mut output);
         }
@@ -2,52 +2,76 @@
 
 use libc;
 
-use compositor::{COMPOSITOR_PTR, Compositor};
+use compositor::{Compositor, CompositorHandle};
 use events::pointer_events::{AbsoluteMotionEvent, AxisEvent, ButtonEvent, MotionEvent};
 use types::PointerHandle;
 
 use wlroots_sys::{wlr_event_pointer_axis, wlr_event_pointer_button, wlr_event_pointer_motion,
                   wlr_input_device};
 
-pub trait PointerHandler {
+pub trait PointerHandler<T = ()> {
     /// Callback that is triggered when the pointer moves.
-    fn on_motion(&mut self, &mut Compositor, &mut PointerHandle, &MotionEvent) {}
+    fn on_motion(&mut self, &mut Compositor<T>, &mut PointerHandle, &MotionEvent) {}
 
-    fn on_motion_absolute(&mut self, &mut Compositor, &mut PointerHandle, &AbsoluteMotionEvent) {}
+    fn on_motion_absolute(&mut self,
+                          &mut Compositor<T>,
+                          &mut PointerHandle,
+                          &AbsoluteMotionEvent) {
+    }
 
     /// Callback that is triggered when the buttons on the pointer are pressed.
-    fn on_button(&mut self, &mut Compositor, &mut PointerHandle, &ButtonEvent) {}
+    fn on_button(&mut self, &mut Compositor<T>, &mut PointerHandle, &ButtonEvent) {}
 
-    fn on_axis(&mut self, &mut Compositor, &mut PointerHandle, &AxisEvent) {}
+    fn on_axis(&mut self, &mut Compositor<T>, &mut PointerHandle, &AxisEvent) {}
 }
 
-wayland_listener!(PointerWrapper, (PointerHandle, Box<PointerHandler>), [
-    button_listener => key_notify: |this: &mut PointerWrapper, data: *mut libc::c_void,| unsafe {
+wayland_listener!(PointerWrapper<T>, (PointerHandle,
+                                      Box<PointerHandler<T>>,
+                                      CompositorHandle<T>), [
+    button_listener => key_notify: |this: &mut PointerWrapper<T>, data: *mut libc::c_void,| unsafe {
         let event = ButtonEvent::from_ptr(data as *mut wlr_event_pointer_button);
-        let compositor = &mut *COMPOSITOR_PTR;
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
         this.data.1.on_button(compositor, &mut this.data.0, &event)
     };
-    motion_listener => motion_notify:  |this: &mut PointerWrapper, data: *mut libc::c_void,|
+    motion_listener => motion_notify:  |this: &mut PointerWrapper<T>, data: *mut libc::c_void,|
     unsafe {
         let event = MotionEvent::from_ptr(data as *mut wlr_event_pointer_motion);
-        let compositor = &mut *COMPOSITOR_PTR;
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
         this.data.1.on_motion(compositor, &mut this.data.0, &event)
     };
     motion_absolute_listener => motion_absolute_notify:
-    |this: &mut PointerWrapper, data: *mut libc::c_void,| unsafe {
+    |this: &mut PointerWrapper<T>, data: *mut libc::c_void,| unsafe {
         let event = AbsoluteMotionEvent::from_ptr(data as *mut _);
-        let compositor = &mut *COMPOSITOR_PTR;
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
         this.data.1.on_motion_absolute(compositor, &mut this.data.0, &event)
     };
-    axis_listener => axis_notify:  |this: &mut PointerWrapper, data: *mut libc::c_void,| unsafe {
+    axis_listener => axis_notify:  |this: &mut PointerWrapper<T>, data: *mut libc::c_void,| unsafe {
         let event = AxisEvent::from_ptr(data as *mut wlr_event_pointer_axis);
-        let compositor = &mut *COMPOSITOR_PTR;
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
         this.data.1.on_axis(compositor, &mut this.data.0, &event)
     };
 ]);
 
-impl PointerWrapper {
+impl<T> PointerWrapper<T> {
     pub unsafe fn input_device(&self) -> *mut wlr_input_device {
         self.data.0.input_device()
     }
+
+    /// Frees the wrapper, giving back the pointer and its handler.
+    pub(crate) fn into_parts(self: Box<Self>) -> (PointerHandle, Box<PointerHandler<T>>) {
+        let (pointer, pointer_handler, _) = self.data;
+        (pointer, pointer_handler)
+    }
 }
@@ -0,0 +1,60 @@
+//! Handler for tablet pads
+
+use libc;
+
+use compositor::{Compositor, CompositorHandle};
+use events::tablet_pad_events::{TabletPadButtonEvent, TabletPadRingEvent, TabletPadStripEvent};
+use types::TabletPadHandle;
+
+use wlroots_sys::{wlr_event_tablet_pad_button, wlr_event_tablet_pad_ring,
+                  wlr_event_tablet_pad_strip, wlr_input_device};
+
+pub trait TabletPadHandler<T = ()> {
+    /// Callback that is triggered when a button on the pad is pressed.
+    fn on_button(&mut self, &mut Compositor<T>, &mut TabletPadHandle, &TabletPadButtonEvent) {}
+
+    /// Callback that is triggered when a ring on the pad is turned.
+    fn on_ring(&mut self, &mut Compositor<T>, &mut TabletPadHandle, &TabletPadRingEvent) {}
+
+    /// Callback that is triggered when a finger moves along a strip on the
+    /// pad.
+    fn on_strip(&mut self, &mut Compositor<T>, &mut TabletPadHandle, &TabletPadStripEvent) {}
+}
+
+wayland_listener!(TabletPadWrapper<T>, (TabletPadHandle,
+                                        Box<TabletPadHandler<T>>,
+                                        CompositorHandle<T>), [
+    button_listener => button_notify: |this: &mut TabletPadWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TabletPadButtonEvent::from_ptr(data as *mut wlr_event_tablet_pad_button);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_button(compositor, &mut this.data.0, &event)
+    };
+    ring_listener => ring_notify: |this: &mut TabletPadWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TabletPadRingEvent::from_ptr(data as *mut wlr_event_tablet_pad_ring);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_ring(compositor, &mut this.data.0, &event)
+    };
+    strip_listener => strip_notify: |this: &mut TabletPadWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TabletPadStripEvent::from_ptr(data as *mut wlr_event_tablet_pad_strip);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_strip(compositor, &mut this.data.0, &event)
+    };
+]);
+
+impl<T> TabletPadWrapper<T> {
+    pub unsafe fn input_device(&self) -> *mut wlr_input_device {
+        self.data.0.input_device()
+    }
+}
@@ -0,0 +1,79 @@
+//! Handler for tablet tools
+
+use libc;
+
+use compositor::{Compositor, CompositorHandle};
+use events::tablet_tool_events::{TabletToolAxisEvent, TabletToolButtonEvent,
+                                 TabletToolProximityEvent, TabletToolTipEvent};
+use types::TabletToolHandle;
+
+use wlroots_sys::{wlr_event_tablet_tool_axis, wlr_event_tablet_tool_button,
+                  wlr_event_tablet_tool_proximity, wlr_event_tablet_tool_tip, wlr_input_device};
+
+pub trait TabletToolHandler<T = ()> {
+    /// Callback that is triggered when the tool moves, or any of its other
+    /// axes (e.g pressure or tilt) change.
+    fn on_axis(&mut self, &mut Compositor<T>, &mut TabletToolHandle, &TabletToolAxisEvent) {}
+
+    /// Callback that is triggered when the tool comes into or leaves the
+    /// proximity of the tablet.
+    fn on_proximity(&mut self,
+                    &mut Compositor<T>,
+                    &mut TabletToolHandle,
+                    &TabletToolProximityEvent) {
+    }
+
+    /// Callback that is triggered when the tip touches or leaves the tablet.
+    fn on_tip(&mut self, &mut Compositor<T>, &mut TabletToolHandle, &TabletToolTipEvent) {}
+
+    /// Callback that is triggered when a button on the tool is pressed.
+    fn on_button(&mut self, &mut Compositor<T>, &mut TabletToolHandle, &TabletToolButtonEvent) {}
+}
+
+wayland_listener!(TabletToolWrapper<T>, (TabletToolHandle,
+                                         Box<TabletToolHandler<T>>,
+                                         CompositorHandle<T>), [
+    axis_listener => axis_notify: |this: &mut TabletToolWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TabletToolAxisEvent::from_ptr(data as *mut wlr_event_tablet_tool_axis);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_axis(compositor, &mut this.data.0, &event)
+    };
+    proximity_listener => proximity_notify:
+    |this: &mut TabletToolWrapper<T>, data: *mut libc::c_void,| unsafe {
+        let event =
+            TabletToolProximityEvent::from_ptr(data as *mut wlr_event_tablet_tool_proximity);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_proximity(compositor, &mut this.data.0, &event)
+    };
+    tip_listener => tip_notify: |this: &mut TabletToolWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TabletToolTipEvent::from_ptr(data as *mut wlr_event_tablet_tool_tip);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_tip(compositor, &mut this.data.0, &event)
+    };
+    button_listener => button_notify: |this: &mut TabletToolWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TabletToolButtonEvent::from_ptr(data as *mut wlr_event_tablet_tool_button);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_button(compositor, &mut this.data.0, &event)
+    };
+]);
+
+impl<T> TabletToolWrapper<T> {
+    pub unsafe fn input_device(&self) -> *mut wlr_input_device {
+        self.data.0.input_device()
+    }
+}
@@ -0,0 +1,67 @@
+//! Handler for touch screens
+
+use libc;
+
+use compositor::{Compositor, CompositorHandle};
+use events::touch_events::{TouchCancelEvent, TouchDownEvent, TouchMotionEvent, TouchUpEvent};
+use types::TouchHandle;
+
+use wlroots_sys::{wlr_event_touch_cancel, wlr_event_touch_down, wlr_event_touch_motion,
+                  wlr_event_touch_up, wlr_input_device};
+
+pub trait TouchHandler<T = ()> {
+    /// Callback that is triggered when a finger touches the screen.
+    fn on_down(&mut self, &mut Compositor<T>, &mut TouchHandle, &TouchDownEvent) {}
+
+    /// Callback that is triggered when a finger is lifted from the screen.
+    fn on_up(&mut self, &mut Compositor<T>, &mut TouchHandle, &TouchUpEvent) {}
+
+    /// Callback that is triggered when a finger moves on the screen.
+    fn on_motion(&mut self, &mut Compositor<T>, &mut TouchHandle, &TouchMotionEvent) {}
+
+    /// Callback that is triggered when a touch point is cancelled.
+    fn on_cancel(&mut self, &mut Compositor<T>, &mut TouchHandle, &TouchCancelEvent) {}
+}
+
+wayland_listener!(TouchWrapper<T>, (TouchHandle, Box<TouchHandler<T>>, CompositorHandle<T>), [
+    down_listener => down_notify: |this: &mut TouchWrapper<T>, data: *mut libc::c_void,| unsafe {
+        let event = TouchDownEvent::from_ptr(data as *mut wlr_event_touch_down);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_down(compositor, &mut this.data.0, &event)
+    };
+    up_listener => up_notify: |this: &mut TouchWrapper<T>, data: *mut libc::c_void,| unsafe {
+        let event = TouchUpEvent::from_ptr(data as *mut wlr_event_touch_up);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_up(compositor, &mut this.data.0, &event)
+    };
+    motion_listener => motion_notify: |this: &mut TouchWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TouchMotionEvent::from_ptr(data as *mut wlr_event_touch_motion);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_motion(compositor, &mut this.data.0, &event)
+    };
+    cancel_listener => cancel_notify: |this: &mut TouchWrapper<T>, data: *mut libc::c_void,|
+    unsafe {
+        let event = TouchCancelEvent::from_ptr(data as *mut wlr_event_touch_cancel);
+        let compositor = match this.data.2.get() {
+            Some(compositor) => compositor,
+            None => return
+        };
+        this.data.1.on_cancel(compositor, &mut this.data.0, &event)
+    };
+]);
+
+impl<T> TouchWrapper<T> {
+    pub unsafe fn input_device(&self) -> *mut wlr_input_device {
+        self.data.0.input_device()
+    }
+}
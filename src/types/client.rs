@@ -0,0 +1,130 @@
+//! Wrapper around a client connected to the compositor.
+
+use libc::{gid_t, pid_t, uid_t};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use wayland_sys::server::{WAYLAND_SERVER_HANDLE, wl_client};
+
+/// A client that is connected to the compositor.
+///
+/// The client is owned by the Wayland display, this is only a handle to it.
+/// Once the client disconnects the handle stays valid, but it can't be used
+/// to do anything with the client anymore.
+///
+/// Use `Compositor::clients` to get the clients that are connected.
+#[derive(Debug, Clone)]
+pub struct Client {
+    client: *mut wl_client,
+    pid: pid_t,
+    uid: uid_t,
+    gid: gid_t,
+    /// Shared by every handle to this client, set to false once it's destroyed.
+    alive: Rc<Cell<bool>>
+}
+
+impl Client {
+    /// Makes a handle to a client that just connected.
+    pub(crate) unsafe fn from_ptr(client: *mut wl_client) -> Self {
+        let (mut pid, mut uid, mut gid) = (0, 0, 0);
+        ffi_dispatch!(WAYLAND_SERVER_HANDLE,
+                      wl_client_get_credentials,
+                      client,
+                      &mut pid,
+                      &mut uid,
+                      &mut gid);
+        Client {
+            client,
+            pid,
+            uid,
+            gid,
+            alive: Rc::new(Cell::new(true))
+        }
+    }
+
+    /// Marks the client as destroyed.
+    pub(crate) fn destroyed(&self) {
+        self.alive.set(false)
+    }
+
+    /// Gets the process id of the client, as seen when it connected.
+    pub fn pid(&self) -> pid_t {
+        self.pid
+    }
+
+    /// Gets the user id of the client, as seen when it connected.
+    pub fn uid(&self) -> uid_t {
+        self.uid
+    }
+
+    /// Gets the group id of the client, as seen when it connected.
+    pub fn gid(&self) -> gid_t {
+        self.gid
+    }
+
+    /// Determines if the client is still connected.
+    pub fn is_alive(&self) -> bool {
+        self.alive.get()
+    }
+
+    /// Disconnects the client, destroying everything it created.
+    ///
+    /// Does nothing if the client is already disconnected.
+    pub fn disconnect(&self) {
+        if self.is_alive() {
+            unsafe {
+                ffi_dispatch!(WAYLAND_SERVER_HANDLE, wl_client_destroy, self.client);
+            }
+        }
+    }
+
+    /// Gets the pointer to the client.
+    ///
+    /// # Unsafety
+    /// The pointer dangles once the client is no longer alive.
+    pub unsafe fn as_ptr(&self) -> *mut wl_client {
+        self.client
+    }
+}
+
+/// A view of a client that can't disconnect it, given to callbacks that run
+/// while the display is dispatching the client (e.g the global filter).
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    client: Client
+}
+
+impl ClientInfo {
+    pub(crate) fn new(client: Client) -> Self {
+        ClientInfo { client }
+    }
+
+    /// Gets the process id of the client, as seen when it connected.
+    pub fn pid(&self) -> pid_t {
+        self.client.pid()
+    }
+
+    /// Gets the user id of the client, as seen when it connected.
+    pub fn uid(&self) -> uid_t {
+        self.client.uid()
+    }
+
+    /// Gets the group id of the client, as seen when it connected.
+    pub fn gid(&self) -> gid_t {
+        self.client.gid()
+    }
+
+    /// Determines if this is the client behind the handle.
+    pub fn is(&self, client: &Client) -> bool {
+        self.client == *client
+    }
+}
+
+impl PartialEq for Client {
+    /// Two handles are equal if they refer to the same connection.
+    fn eq(&self, other: &Client) -> bool {
+        Rc::ptr_eq(&self.alive, &other.alive)
+    }
+}
+
+impl Eq for Client {}
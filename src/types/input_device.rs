@@ -1,7 +1,13 @@
-use wlroots_sys::{wlr_input_device, wlr_input_device_pointer, wlr_input_device_type};
+use std::ffi::CStr;
+use std::fmt;
+
+use types::libinput_config::{LibinputConfig, LibinputConfigError};
+
+use wlroots_sys::{libinput_device, wlr_input_device, wlr_input_device_is_libinput,
+                  wlr_input_device_pointer, wlr_input_device_type,
+                  wlr_libinput_get_device_handle};
 
 /// Wrapper for wlr_input_device
-#[derive(Debug)]
 pub struct InputDevice {
     device: *mut wlr_input_device
 }
@@ -12,6 +18,49 @@ impl InputDevice {
         unsafe { (*self.device).type_ }
     }
 
+    /// Gets the name of the device in UTF-8 (e.g "Logitech MX Master").
+    pub fn name(&self) -> String {
+        unsafe {
+            let name = (*self.device).name;
+            if name.is_null() {
+                return String::new()
+            }
+            CStr::from_ptr(name).to_string_lossy().into_owned()
+        }
+    }
+
+    /// Gets the vendor ID of the device, or 0 if it's unknown.
+    pub fn vendor(&self) -> i32 {
+        unsafe { (*self.device).vendor }
+    }
+
+    /// Gets the product ID of the device, or 0 if it's unknown.
+    pub fn product(&self) -> i32 {
+        unsafe { (*self.device).product }
+    }
+
+    /// Gets the libinput handle of the device, if it comes from the libinput
+    /// backend.
+    pub fn libinput_device(&self) -> Option<*mut libinput_device> {
+        unsafe {
+            if wlr_input_device_is_libinput(self.device) {
+                Some(wlr_libinput_get_device_handle(self.device))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Gets the libinput configuration of the device.
+    ///
+    /// Returns `LibinputConfigError::NotLibinput` if the device doesn't come
+    /// from the libinput backend (e.g with the X11 backend).
+    pub fn libinput_config(&mut self) -> Result<LibinputConfig, LibinputConfigError> {
+        self.libinput_device()
+            .map(|device| unsafe { LibinputConfig::from_ptr(device) })
+            .ok_or(LibinputConfigError::NotLibinput)
+    }
+
     // TODO Wrapper around the union
     pub unsafe fn dev_union(&self) -> wlr_input_device_pointer {
         (*self.device).__bindgen_anon_1
@@ -25,3 +74,15 @@ impl InputDevice {
         self.device
     }
 }
+
+impl fmt::Debug for InputDevice {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("InputDevice")
+            .field("name", &self.name())
+            .field("type", &self.dev_type())
+            .field("vendor", &format_args!("{:#06x}", self.vendor()))
+            .field("product", &format_args!("{:#06x}", self.product()))
+            .field("libinput", &self.libinput_device().is_some())
+            .finish()
+    }
+}
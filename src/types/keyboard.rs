@@ -1,12 +1,32 @@
+use std::cell::{Cell, RefCell};
 use std::fmt;
-use wlroots_sys::{wlr_input_device, wlr_keyboard, wlr_keyboard_get_modifiers, wlr_keyboard_led,
-                  wlr_keyboard_led_update, wlr_keyboard_modifier, wlr_keyboard_set_keymap,
-                  xkb_keymap};
+use std::rc::Rc;
+
+use event_loop::TimerHandle;
+use types::Keymap;
+use wlroots_sys::{wlr_event_keyboard_key, wlr_input_device, wlr_keyboard,
+                  wlr_keyboard_get_modifiers, wlr_keyboard_led, wlr_keyboard_led_update,
+                  wlr_keyboard_modifier, wlr_keyboard_set_keymap};
 
 #[derive(Debug)]
 pub struct KeyboardHandle {
     device: *mut wlr_input_device,
-    keyboard: *mut wlr_keyboard
+    keyboard: *mut wlr_keyboard,
+    /// Whether held keys are repeated by the compositor.
+    repeat_enabled: bool,
+    repeat: Rc<KeyRepeat>
+}
+
+/// The key repeat of a keyboard.
+///
+/// NOTE This is shared with the compositor, so that it can cancel the repeat
+/// while the keyboard is borrowed by its handler.
+#[derive(Default)]
+pub(crate) struct KeyRepeat {
+    /// The key that is being repeated, if any.
+    key: Cell<Option<wlr_event_keyboard_key>>,
+    /// The timer that repeats the key, once it's been created.
+    timer: RefCell<Option<TimerHandle>>
 }
 
 impl KeyboardHandle {
@@ -15,7 +35,12 @@ impl KeyboardHandle {
         match (*device).type_ {
             WLR_INPUT_DEVICE_KEYBOARD => {
                 let keyboard = (*device).__bindgen_anon_1.keyboard;
-                Some(KeyboardHandle { device, keyboard })
+                Some(KeyboardHandle {
+                         device,
+                         keyboard,
+                         repeat_enabled: false,
+                         repeat: Rc::new(KeyRepeat::default())
+                     })
             }
             _ => None,
         }
@@ -25,10 +50,12 @@ impl KeyboardHandle {
         self.keyboard
     }
 
-    // TODO: Implement keymap wrapper?
-    pub fn set_keymap(&mut self, keymap: *mut xkb_keymap) {
+    /// Sets the keymap of the keyboard, which decides what its keys mean.
+    ///
+    /// The keyboard keeps its own reference to the keymap.
+    pub fn set_keymap(&mut self, keymap: &Keymap) {
         unsafe {
-            wlr_keyboard_set_keymap(self.keyboard, keymap);
+            wlr_keyboard_set_keymap(self.keyboard, keymap.as_ptr());
         }
     }
 
@@ -42,11 +69,97 @@ impl KeyboardHandle {
         unsafe { KeyboardModifier::from_bits_truncate(wlr_keyboard_get_modifiers(self.keyboard)) }
     }
 
+    /// Gets the keymap of the keyboard, if it has one.
+    pub fn keymap(&self) -> Option<Keymap> {
+        unsafe {
+            let keymap = (*self.keyboard).keymap;
+            if keymap.is_null() {
+                None
+            } else {
+                Some(Keymap::from_ptr(keymap))
+            }
+        }
+    }
+
+    /// Gets the repeat rate of the keyboard in keys per second, and the delay
+    /// in milliseconds before a held key starts repeating.
+    ///
+    /// A rate of 0 means keys don't repeat.
+    pub fn repeat_info(&self) -> (i32, i32) {
+        unsafe {
+            let repeat_info = (*self.keyboard).repeat_info;
+            (repeat_info.rate, repeat_info.delay)
+        }
+    }
+
+    /// Sets whether held keys are repeated.
+    ///
+    /// wlroots leaves key repeat to the clients, so by default the compositor
+    /// only gets one `KeyboardHandler::on_key` per press. When this is
+    /// enabled, `on_key` is called again with `KeyEvent::repeated` set for as
+    /// long as a key is held, using the repeat rate and delay of the keyboard.
+    pub fn set_repeat_enabled(&mut self, enabled: bool) {
+        self.repeat_enabled = enabled;
+        if !enabled {
+            self.cancel_repeat()
+        }
+    }
+
+    /// Determines if held keys are repeated.
+    pub fn repeat_enabled(&self) -> bool {
+        self.repeat_enabled
+    }
+
+    /// Stops repeating the key that is held down, if any.
+    ///
+    /// Call this when the keyboard focus changes, so that the new focus
+    /// doesn't get the keys meant for the old one. The key repeats again
+    /// the next time it's pressed.
+    pub fn cancel_repeat(&mut self) {
+        self.repeat.cancel()
+    }
+
+    pub(crate) fn repeat(&self) -> &Rc<KeyRepeat> {
+        &self.repeat
+    }
+
     pub unsafe fn input_device(&self) -> *mut wlr_input_device {
         self.device
     }
 }
 
+impl KeyRepeat {
+    /// Gets the key that is being repeated, if any.
+    pub(crate) fn key(&self) -> Option<wlr_event_keyboard_key> {
+        self.key.get()
+    }
+
+    /// Starts repeating a key. The timer has to be armed by the caller.
+    pub(crate) fn start(&self, key: wlr_event_keyboard_key) {
+        self.key.set(Some(key))
+    }
+
+    /// Stops repeating the key and disarms the timer.
+    pub(crate) fn cancel(&self) {
+        self.key.set(None);
+        if let Some(ref timer) = *self.timer.borrow() {
+            timer.cancel()
+        }
+    }
+
+    pub(crate) fn set_timer(&self, timer: TimerHandle) {
+        *self.timer.borrow_mut() = Some(timer)
+    }
+}
+
+impl fmt::Debug for KeyRepeat {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("KeyRepeat")
+            .field("key", &self.key.get())
+            .finish()
+    }
+}
+
 bitflags! {
     pub struct KeyboardLed: u32 {
         const WLR_LED_NUM_LOCK = wlr_keyboard_led::WLR_LED_NUM_LOCK as u32;
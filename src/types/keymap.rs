@@ -0,0 +1,179 @@
+//! Safe wrapper around XKB keymaps, which decide what the keys of a
+//! keyboard mean (e.g the layout).
+
+use libc::c_char;
+use std::{error, fmt, io, ptr};
+use std::ffi::CString;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use wlroots_sys::{xkb_context, xkb_context_new, xkb_context_unref, xkb_keymap,
+                  xkb_keymap_new_from_names, xkb_keymap_new_from_string, xkb_keymap_ref,
+                  xkb_keymap_unref, xkb_rule_names};
+use wlroots_sys::xkb_context_flags::*;
+use wlroots_sys::xkb_keymap_compile_flags::*;
+use wlroots_sys::xkb_keymap_format::*;
+
+/// Errors that can occur while making a `Keymap`.
+#[derive(Debug)]
+pub enum KeymapError {
+    /// The XKB context could not be created.
+    Context,
+    /// The keymap file could not be read.
+    Io(io::Error),
+    /// One of the names, or the keymap itself, contained a nul byte.
+    Nul,
+    /// XKB could not compile the keymap.
+    /// The reason is logged by xkbcommon.
+    Compile
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeymapError::Io(ref err) => write!(formatter, "Could not read keymap: {}", err),
+            _ => write!(formatter, "{}", error::Error::description(self))
+        }
+    }
+}
+
+impl error::Error for KeymapError {
+    fn description(&self) -> &str {
+        use self::KeymapError::*;
+        match *self {
+            Context => "Failed to create XKB context",
+            Io(_) => "Could not read keymap",
+            Nul => "Keymap contained a nul byte",
+            Compile => "Could not compile keymap"
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            KeymapError::Io(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for KeymapError {
+    fn from(err: io::Error) -> Self {
+        KeymapError::Io(err)
+    }
+}
+
+/// The RMLVO names a keymap is compiled from (e.g `layout: Some("us")`).
+///
+/// Names that are `None` are taken from the matching `XKB_DEFAULT_*`
+/// environment variable, or the system default if that is not set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeymapNames {
+    pub rules: Option<String>,
+    pub model: Option<String>,
+    pub layout: Option<String>,
+    pub variant: Option<String>,
+    pub options: Option<String>
+}
+
+/// A compiled XKB keymap, which can be given to any number of keyboards.
+#[derive(Debug)]
+pub struct Keymap {
+    keymap: *mut xkb_keymap
+}
+
+impl Keymap {
+    /// Compiles a keymap from RMLVO names.
+    pub fn from_names(names: &KeymapNames) -> Result<Self, KeymapError> {
+        fn to_c(name: &Option<String>) -> Result<Option<CString>, KeymapError> {
+            match *name {
+                Some(ref name) => {
+                    CString::new(name.as_str()).map(Some).map_err(|_| KeymapError::Nul)
+                }
+                None => Ok(None)
+            }
+        }
+        fn as_ptr(name: &Option<CString>) -> *const c_char {
+            name.as_ref().map(|name| name.as_ptr()).unwrap_or(ptr::null())
+        }
+        let rules = to_c(&names.rules)?;
+        let model = to_c(&names.model)?;
+        let layout = to_c(&names.layout)?;
+        let variant = to_c(&names.variant)?;
+        let options = to_c(&names.options)?;
+        let names = xkb_rule_names {
+            rules: as_ptr(&rules),
+            model: as_ptr(&model),
+            layout: as_ptr(&layout),
+            variant: as_ptr(&variant),
+            options: as_ptr(&options)
+        };
+        unsafe {
+            Keymap::compile(|context| {
+                                xkb_keymap_new_from_names(context,
+                                                          &names,
+                                                          XKB_KEYMAP_COMPILE_NO_FLAGS)
+                            })
+        }
+    }
+
+    /// Compiles a keymap from its text form, e.g the output of `xkbcomp`.
+    pub fn from_string<S: Into<Vec<u8>>>(keymap: S) -> Result<Self, KeymapError> {
+        let keymap = CString::new(keymap).map_err(|_| KeymapError::Nul)?;
+        unsafe {
+            Keymap::compile(|context| {
+                                xkb_keymap_new_from_string(context,
+                                                           keymap.as_ptr(),
+                                                           XKB_KEYMAP_FORMAT_TEXT_V1,
+                                                           XKB_KEYMAP_COMPILE_NO_FLAGS)
+                            })
+        }
+    }
+
+    /// Compiles a keymap from a file containing its text form.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, KeymapError> {
+        let mut keymap = Vec::new();
+        File::open(path)?.read_to_end(&mut keymap)?;
+        Keymap::from_string(keymap)
+    }
+
+    /// Runs `compile` with a new XKB context.
+    unsafe fn compile<F>(compile: F) -> Result<Self, KeymapError>
+        where F: FnOnce(*mut xkb_context) -> *mut xkb_keymap
+    {
+        let context = xkb_context_new(XKB_CONTEXT_NO_FLAGS);
+        if context.is_null() {
+            wlr_log!(L_ERROR, "Failed to create XKB context");
+            return Err(KeymapError::Context)
+        }
+        // NOTE The keymap keeps its own reference to the context.
+        let keymap = compile(context);
+        xkb_context_unref(context);
+        if keymap.is_null() {
+            wlr_log!(L_ERROR, "Could not compile keymap");
+            return Err(KeymapError::Compile)
+        }
+        Ok(Keymap { keymap })
+    }
+
+    /// Makes a new reference to an existing keymap.
+    pub(crate) unsafe fn from_ptr(keymap: *mut xkb_keymap) -> Self {
+        Keymap { keymap: xkb_keymap_ref(keymap) }
+    }
+
+    pub unsafe fn as_ptr(&self) -> *mut xkb_keymap {
+        self.keymap
+    }
+}
+
+impl Clone for Keymap {
+    fn clone(&self) -> Self {
+        unsafe { Keymap { keymap: xkb_keymap_ref(self.keymap) } }
+    }
+}
+
+impl Drop for Keymap {
+    fn drop(&mut self) {
+        unsafe { xkb_keymap_unref(self.keymap) }
+    }
+}
@@ -0,0 +1,167 @@
+//! Configuration of devices that come from the libinput backend (e.g the
+//! acceleration of a mouse, or tap-to-click on a touchpad).
+
+use libc::c_int;
+use std::{error, fmt};
+use std::marker::PhantomData;
+
+use ffi::{LIBINPUT_CONFIG_STATUS_SUCCESS, LIBINPUT_CONFIG_STATUS_UNSUPPORTED,
+          libinput_device_config_accel_set_profile, libinput_device_config_accel_set_speed,
+          libinput_device_config_click_set_method, libinput_device_config_dwt_set_enabled,
+          libinput_device_config_left_handed_set, libinput_device_config_scroll_set_method,
+          libinput_device_config_scroll_set_natural_scroll_enabled,
+          libinput_device_config_tap_set_drag_enabled, libinput_device_config_tap_set_enabled};
+
+use wlroots_sys::libinput_device;
+
+/// Errors that can occur while configuring a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibinputConfigError {
+    /// The device doesn't come from the libinput backend.
+    NotLibinput,
+    /// The device doesn't have the setting (e.g tap-to-click on a mouse).
+    Unsupported,
+    /// The value is not valid for the setting (e.g a speed above 1).
+    Invalid
+}
+
+impl fmt::Display for LibinputConfigError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", error::Error::description(self))
+    }
+}
+
+impl error::Error for LibinputConfigError {
+    fn description(&self) -> &str {
+        use self::LibinputConfigError::*;
+        match *self {
+            NotLibinput => "Device does not come from libinput",
+            Unsupported => "Setting is not supported by the device",
+            Invalid => "Value is not valid for the setting"
+        }
+    }
+}
+
+/// How the motion of a pointer is accelerated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelProfile {
+    /// The pointer moves at the same speed as the device, scaled by the
+    /// acceleration speed.
+    Flat,
+    /// The pointer moves faster the faster the device moves.
+    Adaptive
+}
+
+/// How a touchpad is scrolled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollMethod {
+    NoScroll,
+    /// Moving two fingers scrolls.
+    TwoFinger,
+    /// Moving a finger along the edge scrolls.
+    Edge,
+    /// Moving the device while a button is held scrolls.
+    OnButtonDown
+}
+
+/// How a touchpad without physical buttons decides which button is clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickMethod {
+    NoClick,
+    /// The area of the touchpad that is clicked decides the button.
+    ButtonAreas,
+    /// The number of fingers on the touchpad decides the button.
+    Clickfinger
+}
+
+/// The libinput configuration of a device.
+///
+/// Get it with `InputDevice::libinput_config` or
+/// `PointerHandle::libinput_config`.
+#[derive(Debug)]
+pub struct LibinputConfig<'device> {
+    device: *mut libinput_device,
+    phantom: PhantomData<&'device mut ()>
+}
+
+impl<'device> LibinputConfig<'device> {
+    pub(crate) unsafe fn from_ptr(device: *mut libinput_device) -> Self {
+        LibinputConfig {
+            device,
+            phantom: PhantomData
+        }
+    }
+
+    /// Sets how much the motion of the pointer is accelerated, from -1
+    /// (slowest) to 1 (fastest).
+    pub fn set_accel_speed(&mut self, speed: f64) -> Result<(), LibinputConfigError> {
+        unsafe { status(libinput_device_config_accel_set_speed(self.device, speed)) }
+    }
+
+    pub fn set_accel_profile(&mut self, profile: AccelProfile) -> Result<(), LibinputConfigError> {
+        let profile = match profile {
+            AccelProfile::Flat => 1 << 0,
+            AccelProfile::Adaptive => 1 << 1
+        };
+        unsafe { status(libinput_device_config_accel_set_profile(self.device, profile)) }
+    }
+
+    /// Sets whether scrolling moves the content instead of the view, like on
+    /// a touch screen.
+    pub fn set_natural_scroll(&mut self, enabled: bool) -> Result<(), LibinputConfigError> {
+        unsafe {
+            status(libinput_device_config_scroll_set_natural_scroll_enabled(self.device,
+                                                                            enabled as c_int))
+        }
+    }
+
+    /// Sets whether tapping a touchpad clicks.
+    pub fn set_tap(&mut self, enabled: bool) -> Result<(), LibinputConfigError> {
+        unsafe { status(libinput_device_config_tap_set_enabled(self.device, enabled as c_int)) }
+    }
+
+    /// Sets whether tapping a touchpad and then moving the finger drags.
+    pub fn set_tap_drag(&mut self, enabled: bool) -> Result<(), LibinputConfigError> {
+        unsafe {
+            status(libinput_device_config_tap_set_drag_enabled(self.device, enabled as c_int))
+        }
+    }
+
+    /// Sets whether a touchpad is disabled while typing on a keyboard.
+    pub fn set_disable_while_typing(&mut self, enabled: bool) -> Result<(), LibinputConfigError> {
+        unsafe { status(libinput_device_config_dwt_set_enabled(self.device, enabled as c_int)) }
+    }
+
+    /// Sets whether the left and right buttons are swapped.
+    pub fn set_left_handed(&mut self, enabled: bool) -> Result<(), LibinputConfigError> {
+        unsafe { status(libinput_device_config_left_handed_set(self.device, enabled as c_int)) }
+    }
+
+    pub fn set_scroll_method(&mut self, method: ScrollMethod) -> Result<(), LibinputConfigError> {
+        let method = match method {
+            ScrollMethod::NoScroll => 0,
+            ScrollMethod::TwoFinger => 1 << 0,
+            ScrollMethod::Edge => 1 << 1,
+            ScrollMethod::OnButtonDown => 1 << 2
+        };
+        unsafe { status(libinput_device_config_scroll_set_method(self.device, method)) }
+    }
+
+    pub fn set_click_method(&mut self, method: ClickMethod) -> Result<(), LibinputConfigError> {
+        let method = match method {
+            ClickMethod::NoClick => 0,
+            ClickMethod::ButtonAreas => 1 << 0,
+            ClickMethod::Clickfinger => 1 << 1
+        };
+        unsafe { status(libinput_device_config_click_set_method(self.device, method)) }
+    }
+}
+
+/// Converts the status libinput returns for a setting.
+fn status(status: c_int) -> Result<(), LibinputConfigError> {
+    match status {
+        LIBINPUT_CONFIG_STATUS_SUCCESS => Ok(()),
+        LIBINPUT_CONFIG_STATUS_UNSUPPORTED => Err(LibinputConfigError::Unsupported),
+        _ => Err(LibinputConfigError::Invalid)
+    }
+}
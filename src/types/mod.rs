@@ -1,11 +1,23 @@
+pub mod client;
 pub mod cursor;
 pub mod pointer;
 pub mod input_device;
 pub mod keyboard;
+pub mod keymap;
+pub mod libinput_config;
 pub mod output;
+pub mod tablet_pad;
+pub mod tablet_tool;
+pub mod touch;
 
+pub use self::client::*;
 pub use self::cursor::*;
 pub use self::input_device::*;
 pub use self::keyboard::*;
+pub use self::keymap::*;
+pub use self::libinput_config::*;
 pub use self::output::*;
 pub use self::pointer::*;
+pub use self::tablet_pad::*;
+pub use self::tablet_tool::*;
+pub use self::touch::*;
@@ -1,3 +1,6 @@
+use types::input_device::InputDevice;
+use types::libinput_config::{LibinputConfig, LibinputConfigError};
+
 use wlroots_sys::{wlr_input_device, wlr_pointer};
 
 /// A wlr_input_device that is guaranteed to be a pointer.
@@ -28,6 +31,20 @@ impl PointerHandle {
         self.device
     }
 
+    /// Gets the libinput configuration of the pointer (e.g to set the
+    /// acceleration of a mouse).
+    ///
+    /// Returns `LibinputConfigError::NotLibinput` if the pointer doesn't
+    /// come from the libinput backend.
+    pub fn libinput_config(&mut self) -> Result<LibinputConfig, LibinputConfigError> {
+        unsafe {
+            InputDevice::from_ptr(self.device)
+                .libinput_device()
+                .map(|device| LibinputConfig::from_ptr(device))
+                .ok_or(LibinputConfigError::NotLibinput)
+        }
+    }
+
     pub unsafe fn pointer(&self) -> *mut wlr_pointer {
         self.pointer
     }
@@ -0,0 +1,35 @@
+use wlroots_sys::{wlr_input_device, wlr_tablet_pad};
+
+/// A wlr_input_device that is guaranteed to be a tablet pad.
+#[derive(Debug)]
+pub struct TabletPadHandle {
+    /// The device that refers to this tablet pad
+    device: *mut wlr_input_device,
+    /// The underlying tablet pad data
+    tablet_pad: *mut wlr_tablet_pad
+}
+
+impl TabletPadHandle {
+    /// Tries to convert an input device to a tablet pad
+    ///
+    /// Returns none if it is of a different input varient.
+    pub(crate) unsafe fn from_input_device(device: *mut wlr_input_device) -> Option<Self> {
+        use wlroots_sys::wlr_input_device_type::*;
+        match (*device).type_ {
+            WLR_INPUT_DEVICE_TABLET_PAD => {
+                let tablet_pad = (*device).__bindgen_anon_1.tablet_pad;
+                Some(TabletPadHandle { device, tablet_pad })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the wlr_input_device associated with this tablet pad
+    pub unsafe fn input_device(&self) -> *mut wlr_input_device {
+        self.device
+    }
+
+    pub unsafe fn tablet_pad(&self) -> *mut wlr_tablet_pad {
+        self.tablet_pad
+    }
+}
@@ -0,0 +1,35 @@
+use wlroots_sys::{wlr_input_device, wlr_tablet_tool};
+
+/// A wlr_input_device that is guaranteed to be a tablet tool (e.g a pen).
+#[derive(Debug)]
+pub struct TabletToolHandle {
+    /// The device that refers to this tablet tool
+    device: *mut wlr_input_device,
+    /// The underlying tablet tool data
+    tablet_tool: *mut wlr_tablet_tool
+}
+
+impl TabletToolHandle {
+    /// Tries to convert an input device to a tablet tool
+    ///
+    /// Returns none if it is of a different input varient.
+    pub(crate) unsafe fn from_input_device(device: *mut wlr_input_device) -> Option<Self> {
+        use wlroots_sys::wlr_input_device_type::*;
+        match (*device).type_ {
+            WLR_INPUT_DEVICE_TABLET_TOOL => {
+                let tablet_tool = (*device).__bindgen_anon_1.tablet_tool;
+                Some(TabletToolHandle { device, tablet_tool })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the wlr_input_device associated with this tablet tool
+    pub unsafe fn input_device(&self) -> *mut wlr_input_device {
+        self.device
+    }
+
+    pub unsafe fn tablet_tool(&self) -> *mut wlr_tablet_tool {
+        self.tablet_tool
+    }
+}
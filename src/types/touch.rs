@@ -0,0 +1,35 @@
+use wlroots_sys::{wlr_input_device, wlr_touch};
+
+/// A wlr_input_device that is guaranteed to be a touch screen.
+#[derive(Debug)]
+pub struct TouchHandle {
+    /// The device that refers to this touch screen
+    device: *mut wlr_input_device,
+    /// The underlying touch screen data
+    touch: *mut wlr_touch
+}
+
+impl TouchHandle {
+    /// Tries to convert an input device to a touch screen
+    ///
+    /// Returns none if it is of a different input varient.
+    pub(crate) unsafe fn from_input_device(device: *mut wlr_input_device) -> Option<Self> {
+        use wlroots_sys::wlr_input_device_type::*;
+        match (*device).type_ {
+            WLR_INPUT_DEVICE_TOUCH => {
+                let touch = (*device).__bindgen_anon_1.touch;
+                Some(TouchHandle { device, touch })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the wlr_input_device associated with this touch screen
+    pub unsafe fn input_device(&self) -> *mut wlr_input_device {
+        self.device
+    }
+
+    pub unsafe fn touch(&self) -> *mut wlr_touch {
+        self.touch
+    }
+}
@@ -1,8 +1,17 @@
 //! Utility functions for use within wlroots-rs
 
+use std::any::Any;
+use std::cell::RefCell;
 use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
 use std::process::exit;
 
+thread_local! {
+    /// The first panic that was caught in a callback from C, which hasn't
+    /// been handed back to the compositor yet.
+    static PANIC: RefCell<Option<Box<Any + Send>>> = RefCell::new(None);
+}
+
 /// Converts a Rust string into C string without error handling.
 /// If any error occurs, it is logged and then the program is immediantly
 /// aborted.
@@ -21,3 +30,31 @@ where
         }
     }
 }
+
+/// Runs Rust code that was called from C, catching any panic so that it
+/// doesn't unwind into C (which is undefined behaviour).
+///
+/// The panic is stored until `take_panic` is called, which the compositor
+/// does once control is back in Rust. Only the first panic is kept.
+pub fn handle_unwind<F, R>(func: F) -> Option<R>
+    where F: FnOnce() -> R
+{
+    match panic::catch_unwind(AssertUnwindSafe(func)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            wlr_log!(L_ERROR, "Caught panic from a callback, shutting down the compositor");
+            PANIC.with(|panic| {
+                           let mut panic = panic.borrow_mut();
+                           if panic.is_none() {
+                               *panic = Some(payload)
+                           }
+                       });
+            None
+        }
+    }
+}
+
+/// Takes the panic caught by `handle_unwind`, if there was one.
+pub fn take_panic() -> Option<Box<Any + Send>> {
+    PANIC.with(|panic| panic.borrow_mut().take())
+}